@@ -0,0 +1,166 @@
+use serde::Serialize;
+
+use super::components::GameRng;
+use super::config::Config;
+use super::controller::SnakeController;
+use super::simulation::{Simulation, StepOutcome};
+
+/// How many times over the snake may travel the whole arena without eating before it is
+/// considered stuck. No sensible strategy needs that long between two foods.
+pub const STUCK_LAPS: usize = 2;
+
+/// Whether the snake has gone [`STUCK_LAPS`] times the area of the arena in moves without eating.
+pub fn is_stuck(sim: &Simulation) -> bool {
+    sim.moves_since_food() >= STUCK_LAPS * sim.arena().area()
+}
+
+/// How a benchmarked game ended.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Ending {
+    Won,
+    DiedByWall,
+    DiedBySelf,
+    /// The snake went [`STUCK_LAPS`] times the area of the arena in moves without eating, and
+    /// would probably never eat again.
+    Stuck,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct GameResult {
+    pub len: usize,
+    /// The number of foods eaten.
+    pub foods: usize,
+    pub moves: usize,
+    pub ending: Ending,
+}
+
+/// Statistics over a batch of games played by the same controller.
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub ai: &'static str,
+    pub games: usize,
+    pub avg_len: f64,
+    pub median_len: f64,
+    pub max_len: usize,
+    /// The average number of moves it took to find each food.
+    pub moves_per_food: f64,
+    pub won: usize,
+    pub died_by_wall: usize,
+    pub died_by_self: usize,
+    pub stuck: usize,
+    pub seconds: f64,
+}
+
+/// Plays a whole game without Bevy, following the same rules as the app. Food is placed exactly as
+/// in the first game of an app started with the same config and seed.
+pub fn play_game(controller: &mut dyn SnakeController, config: &Config, seed: u64) -> GameResult {
+    let mut rng = GameRng::new(seed);
+    let mut sim = config.new_game();
+    sim.spawn_food(&mut rng.0);
+    let ending = loop {
+        match sim.step(controller.next_dir(&sim)) {
+            StepOutcome::Moved => {}
+            StepOutcome::Ate => {
+                sim.spawn_food(&mut rng.0);
+            }
+            StepOutcome::DiedByWall => break Ending::DiedByWall,
+            StepOutcome::DiedBySelf => break Ending::DiedBySelf,
+            StepOutcome::Won => break Ending::Won,
+        }
+        if is_stuck(&sim) {
+            break Ending::Stuck;
+        }
+    };
+    GameResult {
+        len: sim.len(),
+        foods: sim.len() - config.starting_len,
+        moves: sim.moves(),
+        ending,
+    }
+}
+
+impl Summary {
+    /// Summarises `results`, which took `seconds` to play. There must be at least one result.
+    pub fn new(ai: &'static str, results: &[GameResult], seconds: f64) -> Self {
+        let games = results.len();
+        let mut lens: Vec<usize> = results.iter().map(|result| result.len).collect();
+        lens.sort_unstable();
+        // With an odd number of games both halves pick the same middle game
+        let median_len = (lens[(games - 1) / 2] + lens[games / 2]) as f64 / 2.0;
+        let foods: usize = results.iter().map(|result| result.foods).sum();
+        let moves: usize = results.iter().map(|result| result.moves).sum();
+        let count = |ending| {
+            results
+                .iter()
+                .filter(|result| result.ending == ending)
+                .count()
+        };
+        Self {
+            ai,
+            games,
+            avg_len: lens.iter().sum::<usize>() as f64 / games as f64,
+            median_len,
+            max_len: lens[games - 1],
+            moves_per_food: moves as f64 / foods.max(1) as f64,
+            won: count(Ending::Won),
+            died_by_wall: count(Ending::DiedByWall),
+            died_by_self: count(Ending::DiedBySelf),
+            stuck: count(Ending::Stuck),
+            seconds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::Arena;
+    use crate::controller::controller_by_name;
+
+    #[test]
+    fn same_seed_plays_same_game() {
+        let mut first = controller_by_name("astar").unwrap();
+        let mut second = controller_by_name("astar").unwrap();
+        assert_eq!(
+            play_game(&mut *first, &Config::default(), 7),
+            play_game(&mut *second, &Config::default(), 7)
+        );
+    }
+
+    #[test]
+    fn safe_ai_does_not_get_stuck() {
+        // Without food in reach, the safe AI used to chase its tail round the same loop for good
+        let config = Config {
+            arena: Arena::new(8, 8),
+            ..Default::default()
+        };
+        let mut controller = controller_by_name("safe").unwrap();
+        let result = play_game(&mut *controller, &config, 0);
+        assert_eq!(result.ending, Ending::Won);
+    }
+
+    #[test]
+    fn summary_statistics() {
+        let result = |len, ending| GameResult {
+            len,
+            foods: len - 4,
+            moves: 10,
+            ending,
+        };
+        let results = [
+            result(10, Ending::DiedBySelf),
+            result(20, Ending::DiedByWall),
+            result(6, Ending::Stuck),
+            result(8, Ending::DiedBySelf),
+        ];
+        let summary = Summary::new("test", &results, 1.0);
+        assert_eq!(summary.avg_len, 11.0);
+        assert_eq!(summary.median_len, 9.0);
+        assert_eq!(summary.max_len, 20);
+        // 40 moves for 28 foods
+        assert_eq!(summary.moves_per_food, 40.0 / 28.0);
+        assert_eq!(summary.died_by_self, 2);
+        assert_eq!(summary.stuck, 1);
+        assert_eq!(summary.won, 0);
+    }
+}
@@ -0,0 +1,160 @@
+//! Plays many games with each AI, without a window, and reports how well each one did.
+//!
+//! Game `i` of every AI uses seed `i`, so `bevy-ai-snake --ai NAME --seed i` replays it, given the
+//! same config.
+
+use std::time::Instant;
+
+use bevy_ai_snake::bench::{play_game, Summary};
+use bevy_ai_snake::cli::{parse_ai, Args};
+use bevy_ai_snake::config::Config;
+use bevy_ai_snake::controller::{controller_by_name, CONTROLLER_NAMES};
+
+const USAGE: &str = "\
+Usage: bench [OPTIONS]
+
+Options:
+  --games N          Play N games with each AI, 100 by default
+  --ai NAME          An AI to play, can be given more than once. All of them play by default
+  --size WxH         The size of the arena, in cells
+  --config FILE      Read settings from FILE instead of snake.toml
+  --set KEY=VALUE    Override a setting from the config file, e.g. --set arena.width=30
+  --format FORMAT    Print the results as text, csv or json
+  --help             Print this message";
+
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+fn main() {
+    let mut ais = Vec::new();
+    let mut format = Format::Text;
+    let args = Args::parse_with(std::env::args().skip(1), |arg, value| {
+        match arg {
+            // Unlike the game, the benchmark can compare several AIs
+            "--ai" => ais.push(parse_ai(&value()?)?),
+            "--format" => {
+                let value = value()?;
+                format = match value.as_str() {
+                    "text" => Format::Text,
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => {
+                        return Err(format!(
+                            "Unknown format '{}', expected one of: text, csv, json",
+                            value
+                        ))
+                    }
+                };
+            }
+            // Every game is played without a window and as fast as possible, with its own seed
+            "--seed" | "--speed" | "--headless" | "--replay" => {
+                return Err(format!("Unknown argument '{}'", arg))
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })
+    .unwrap_or_else(|err| fail(&format!("{}\n\n{}", err, USAGE)));
+    if args.help {
+        println!("{}", USAGE);
+        return;
+    }
+    let games = args.games.unwrap_or(100);
+    let config =
+        Config::load(args.config_path.as_deref(), &args.overrides).unwrap_or_else(|err| fail(&err));
+    let arena = config.arena;
+    if ais.is_empty() {
+        // A human controller never turns, so there's no point benchmarking it
+        ais = CONTROLLER_NAMES
+            .into_iter()
+            .filter(|&name| name != "human")
+            .filter(|&name| controller_by_name(name).unwrap().supports(arena))
+            .collect();
+    }
+    for &ai in &ais {
+        if !controller_by_name(ai).unwrap().supports(arena) {
+            fail(&format!(
+                "The {} AI can't play in a {}x{} arena",
+                ai, arena.width, arena.height
+            ));
+        }
+    }
+
+    let summaries: Vec<Summary> = ais
+        .into_iter()
+        .map(|ai| {
+            let start = Instant::now();
+            let results: Vec<_> = (0..games as u64)
+                .map(|seed| play_game(&mut *controller_by_name(ai).unwrap(), &config, seed))
+                .collect();
+            Summary::new(ai, &results, start.elapsed().as_secs_f64())
+        })
+        .collect();
+
+    match format {
+        Format::Text => print_table(&summaries),
+        Format::Csv => print_csv(&summaries),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&summaries).unwrap()),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn print_table(summaries: &[Summary]) {
+    println!(
+        "{:<10} {:>6} {:>8} {:>8} {:>6} {:>11} {:>5} {:>5} {:>5} {:>6} {:>9}",
+        "AI",
+        "games",
+        "avg len",
+        "median",
+        "max",
+        "moves/food",
+        "won",
+        "wall",
+        "self",
+        "stuck",
+        "time (s)"
+    );
+    for s in summaries {
+        println!(
+            "{:<10} {:>6} {:>8.1} {:>8.1} {:>6} {:>11.1} {:>5} {:>5} {:>5} {:>6} {:>9.2}",
+            s.ai,
+            s.games,
+            s.avg_len,
+            s.median_len,
+            s.max_len,
+            s.moves_per_food,
+            s.won,
+            s.died_by_wall,
+            s.died_by_self,
+            s.stuck,
+            s.seconds
+        );
+    }
+}
+
+fn print_csv(summaries: &[Summary]) {
+    println!("ai,games,avg_len,median_len,max_len,moves_per_food,won,died_by_wall,died_by_self,stuck,seconds");
+    for s in summaries {
+        println!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            s.ai,
+            s.games,
+            s.avg_len,
+            s.median_len,
+            s.max_len,
+            s.moves_per_food,
+            s.won,
+            s.died_by_wall,
+            s.died_by_self,
+            s.stuck,
+            s.seconds
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use super::components::Arena;
+use super::controller::CONTROLLER_NAMES;
+
+pub const USAGE: &str = "\
+Usage: bevy-ai-snake [OPTIONS]
+
+Options:
+  --ai NAME          The AI that steers the snake: human, greedy, astar, bfs, safe or hamilton
+  --seed N           Seed of the first game, each game after it uses the next seed
+  --size WxH         The size of the arena, in cells
+  --speed N          Moves per second
+  --headless         Run without a window, as fast as possible
+  --games N          Quit after playing N games
+  --replay FILE      Play back a game recorded to FILE, instead of running an AI
+  --config FILE      Read settings from FILE instead of snake.toml
+  --set KEY=VALUE    Override a setting from the config file, e.g. --set arena.width=30
+  --help             Print this message";
+
+/// The command line arguments of the game.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub help: bool,
+    pub headless: bool,
+    /// The name of the AI, one of [`CONTROLLER_NAMES`].
+    pub ai: Option<&'static str>,
+    pub seed: Option<u64>,
+    pub games: Option<usize>,
+    pub replay_path: Option<String>,
+    pub config_path: Option<String>,
+    /// Overrides for the config, including those made by `--size` and `--speed`. See
+    /// [`Config::load`](super::config::Config::load).
+    pub overrides: Vec<String>,
+}
+
+impl Args {
+    /// Parses the arguments, not including the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        Self::parse_with(args, |_, _| Ok(false))
+    }
+
+    /// Like [`Args::parse`], but offers each argument to `extra` first, for other programs to add
+    /// options of their own or handle some differently. `extra` takes the option's value, if it
+    /// has one, from the closure it is given, and returns whether it handled the option.
+    pub fn parse_with(
+        args: impl IntoIterator<Item = String>,
+        mut extra: impl FnMut(&str, &mut dyn FnMut() -> Result<String, String>) -> Result<bool, String>,
+    ) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for '{}'", arg))
+            };
+            if extra(&arg, &mut value)? {
+                continue;
+            }
+            match arg.as_str() {
+                "--help" | "-h" => parsed.help = true,
+                "--headless" => parsed.headless = true,
+                "--ai" => parsed.ai = Some(parse_ai(&value()?)?),
+                "--seed" => {
+                    let seed = value()?;
+                    parsed.seed = Some(seed.parse().map_err(|_| {
+                        format!("Invalid seed '{}', expected a whole number", seed)
+                    })?);
+                }
+                "--size" => {
+                    let Arena { width, height } = value()?.parse()?;
+                    parsed.overrides.push(format!("arena.width={}", width));
+                    parsed.overrides.push(format!("arena.height={}", height));
+                }
+                "--speed" => {
+                    let speed = value()?;
+                    match speed.parse::<f64>() {
+                        Ok(moves) if moves > 0.0 => {
+                            parsed.overrides.push(format!("move_delay={}", 1.0 / moves));
+                        }
+                        _ => {
+                            return Err(format!(
+                                "Invalid speed '{}', expected moves per second",
+                                speed
+                            ))
+                        }
+                    }
+                }
+                "--games" => {
+                    let games = value()?;
+                    match games.parse() {
+                        Ok(games) if games > 0 => parsed.games = Some(games),
+                        _ => return Err(format!("Invalid number of games '{}'", games)),
+                    }
+                }
+                "--replay" => parsed.replay_path = Some(value()?),
+                "--config" => parsed.config_path = Some(value()?),
+                "--set" => parsed.overrides.push(value()?),
+                _ => return Err(format!("Unknown argument '{}'", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Looks up the AI called `name` among the [`CONTROLLER_NAMES`].
+pub fn parse_ai(name: &str) -> Result<&'static str, String> {
+    let ai = CONTROLLER_NAMES.iter().find(|&&ai| ai == name);
+    ai.copied().ok_or_else(|| {
+        format!(
+            "Unknown AI '{}', expected one of: {}",
+            name,
+            CONTROLLER_NAMES.join(", ")
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_all_options() {
+        let args = parse(&[
+            "--ai",
+            "astar",
+            "--seed",
+            "42",
+            "--size",
+            "30x10",
+            "--speed",
+            "20",
+            "--headless",
+            "--games",
+            "5",
+            "--replay",
+            "game.json",
+            "--set",
+            "starting_len=6",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            Args {
+                headless: true,
+                ai: Some("astar"),
+                seed: Some(42),
+                games: Some(5),
+                replay_path: Some("game.json".to_owned()),
+                overrides: vec![
+                    "arena.width=30".to_owned(),
+                    "arena.height=10".to_owned(),
+                    "move_delay=0.05".to_owned(),
+                    "starting_len=6".to_owned(),
+                ],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn reject_invalid_options() {
+        assert!(parse(&["--ai", "clever"]).is_err());
+        assert!(parse(&["--replay"]).is_err());
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--speed", "0"]).is_err());
+        assert!(parse(&["--games", "none"]).is_err());
+        assert!(parse(&["--fast"]).is_err());
+    }
+
+    #[test]
+    fn extra_options_come_first() {
+        let mut ais = Vec::new();
+        let args = ["--ai", "astar", "--ai", "bfs", "--games", "3"];
+        let args = Args::parse_with(args.iter().map(|arg| arg.to_string()), |arg, value| {
+            if arg != "--ai" {
+                return Ok(false);
+            }
+            ais.push(parse_ai(&value()?)?);
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(ais, ["astar", "bfs"]);
+        assert_eq!(args.ai, None);
+        assert_eq!(args.games, Some(3));
+    }
+}
@@ -1,39 +1,64 @@
 use bevy::prelude::*;
 
-use crate::consts::{ARENA_HEIGHT, ARENA_WIDTH};
-
+use super::bench::is_stuck;
 use super::components::*;
+use super::game::end_game;
+use super::simulation::{Simulation, StepOutcome};
+use super::snake::{SnakeMoveTick, SnakeMovement};
 
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(collide_body).add_system(wall_collision);
+        app.add_system(
+            collide_body
+                .after(SnakeMovement)
+                .with_run_criteria(SnakeMoveTick),
+        )
+        .add_system(
+            wall_collision
+                .after(SnakeMovement)
+                .with_run_criteria(SnakeMoveTick),
+        )
+        .add_system(
+            stuck_check
+                .after(SnakeMovement)
+                .with_run_criteria(SnakeMoveTick),
+        );
     }
 }
 
-fn collide_body(body: Res<SnakeBody>, mut game_state: ResMut<GameState>, pos_query: Query<&Pos>) {
-    if *game_state == GameState::Lost {
-        return;
-    }
-    // The first element of the snake body is the head
-    let (head, body) = body.0.split_at(1);
-    let head_pos = pos_query.get(head[0]).unwrap();
-    for body_pos in pos_query.iter_many(body) {
-        if head_pos == body_pos {
-            *game_state = GameState::Lost;
-            println!("You lose");
-        }
+fn collide_body(
+    mut game_state: ResMut<State<GameState>>,
+    mut step_reader: EventReader<StepOutcome>,
+) {
+    if step_reader
+        .iter()
+        .any(|&outcome| outcome == StepOutcome::DiedBySelf)
+    {
+        end_game(&mut game_state, GameState::GameOver);
+        println!("You lose");
     }
 }
 
-fn wall_collision(mut game_state: ResMut<GameState>, head_query: Query<&Pos, With<SnakeHead>>) {
-    if *game_state == GameState::Lost {
-        return;
-    }
-    let head = head_query.single();
-    if head.x < 0 || head.x >= ARENA_WIDTH as i32 || head.y < 0 || head.y >= ARENA_HEIGHT as i32 {
-        *game_state = GameState::Lost;
+fn wall_collision(
+    mut game_state: ResMut<State<GameState>>,
+    mut step_reader: EventReader<StepOutcome>,
+) {
+    if step_reader
+        .iter()
+        .any(|&outcome| outcome == StepOutcome::DiedByWall)
+    {
+        end_game(&mut game_state, GameState::GameOver);
         println!("You lose");
     }
 }
+
+/// Ends the game once the snake is stuck by the same rule as the benchmark, since it would
+/// probably never eat again and a game without a window would never end.
+fn stuck_check(sim: Res<Simulation>, mut game_state: ResMut<State<GameState>>) {
+    if !sim.is_over() && is_stuck(&sim) {
+        end_game(&mut game_state, GameState::GameOver);
+        println!("You got stuck");
+    }
+}
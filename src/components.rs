@@ -1,10 +1,14 @@
 use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use std::str::FromStr;
 
 use crate::consts::{ARENA_HEIGHT, ARENA_WIDTH};
 
 #[derive(Component)]
 pub struct SnakeHead {
-    pub prev_dir: Dir,
     pub next_dir: Dir,
 }
 
@@ -17,28 +21,74 @@ pub struct Size(pub f32);
 #[derive(Component)]
 pub struct Food;
 
-pub struct EatEvent;
-
-#[derive(Component, Default, Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(
+    Component,
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
 pub struct Pos {
     pub x: i32,
     pub y: i32,
 }
 
+/// The dimensions of the grid the game is played on, in cells.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Arena {
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Default, Deref, DerefMut)]
 pub struct SnakeBody(pub Vec<Entity>);
 
-#[derive(Default)]
-pub struct LastTailPos(pub Pos);
+/// The source of all randomness in the game. A given seed and controller always play the same
+/// game.
+#[derive(Clone, Deref, DerefMut)]
+pub struct GameRng(pub StdRng);
+
+/// The seed of the current game. [`GameRng`] is reseeded with it when a game starts, and each game
+/// uses the seed after the previous one's, so that any game can be replayed on its own.
+pub struct GameSeed(pub u64);
 
-#[derive(Eq, PartialEq)]
+/// Marker resource inserted when the game runs without a window. Systems that need a display or
+/// keyboard are left out, and the snake moves every frame instead of on a fixed timestep.
+pub struct Headless;
+
+/// The screens of the game, driven by Bevy's `State`. `Paused` is pushed on top of `Playing`, so
+/// that the game carries on where it left off once the pause is popped. `GameOver` and `Won` are
+/// too, so that a game rewound after it ended can be paused and carried on with.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum GameState {
+    Menu,
     Playing,
-    // Paused,
-    Lost,
+    Paused,
+    /// The snake has died. The final board stays on screen until the restart.
+    GameOver,
+    /// The snake has filled the arena. Otherwise just like `GameOver`.
+    Won,
+}
+
+impl GameState {
+    /// The states a game can end in.
+    pub const ENDED: [GameState; 2] = [GameState::GameOver, GameState::Won];
+
+    /// Whether the game has ended, whether the snake died or won.
+    pub fn is_ended(&self) -> bool {
+        Self::ENDED.contains(self)
+    }
 }
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Dir {
     Left,
     Right,
@@ -46,16 +96,23 @@ pub enum Dir {
     Down,
 }
 
-impl SnakeHead {
-    pub fn default() -> Self {
+impl Default for SnakeHead {
+    fn default() -> Self {
         Self {
-            prev_dir: Dir::Right,
             next_dir: Dir::Right,
         }
     }
 }
 
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
 impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::Left, Dir::Right, Dir::Up, Dir::Down];
+
     pub fn opposite(self) -> Self {
         match self {
             Self::Left => Self::Right,
@@ -66,13 +123,55 @@ impl Dir {
     }
 }
 
+impl Arena {
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    /// The number of cells in the arena.
+    pub const fn area(self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Every cell of the arena, row by row from the bottom left.
+    pub fn cells(self) -> impl Iterator<Item = Pos> {
+        (0..self.height as i32)
+            .flat_map(move |y| (0..self.width as i32).map(move |x| Pos::new(x, y)))
+    }
+
+    /// The index of `pos` in a row by row array of every cell, like the one [`Arena::cells`]
+    /// yields.
+    pub const fn index(self, pos: Pos) -> usize {
+        pos.y as usize * self.width as usize + pos.x as usize
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new(ARENA_WIDTH, ARENA_HEIGHT)
+    }
+}
+
+impl FromStr for Arena {
+    type Err = String;
+
+    /// Parses a size like `20x20`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid arena size '{}', expected WIDTHxHEIGHT", s);
+        let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+        let width = width.parse().map_err(|_| invalid())?;
+        let height = height.parse().map_err(|_| invalid())?;
+        Ok(Self::new(width, height))
+    }
+}
+
 impl Pos {
     pub const fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
 
-    pub const fn in_bounds(self) -> bool {
-        self.x >= 0 && self.y >= 0 && self.x < ARENA_WIDTH as i32 && self.y < ARENA_HEIGHT as i32
+    pub const fn in_bounds(self, arena: Arena) -> bool {
+        self.x >= 0 && self.y >= 0 && self.x < arena.width as i32 && self.y < arena.height as i32
     }
 
     pub fn in_direction(self, direction: Dir) -> Self {
@@ -102,6 +201,23 @@ mod test {
         assert_eq!(pos.in_direction(Dir::Down), down);
     }
 
+    #[test]
+    fn pos_in_bounds() {
+        let arena = Arena::new(10, 6);
+        assert!(Pos::new(0, 0).in_bounds(arena));
+        assert!(Pos::new(9, 5).in_bounds(arena));
+        assert!(!Pos::new(10, 5).in_bounds(arena));
+        assert!(!Pos::new(9, 6).in_bounds(arena));
+        assert!(!Pos::new(-1, 0).in_bounds(arena));
+    }
+
+    #[test]
+    fn arena_from_str() {
+        assert_eq!("10x12".parse(), Ok(Arena::new(10, 12)));
+        assert!("10".parse::<Arena>().is_err());
+        assert!("10xten".parse::<Arena>().is_err());
+    }
+
     #[test]
     fn direction_opposite() {
         assert_eq!(Dir::Left.opposite(), Dir::Right);
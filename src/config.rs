@@ -0,0 +1,287 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::components::{Arena, Dir, Pos};
+use super::consts::*;
+use super::simulation::Simulation;
+
+/// The file the config is read from when no other is given, if it exists.
+pub const DEFAULT_CONFIG_PATH: &str = "snake.toml";
+
+/// Every tunable of the game, read from a TOML file. Anything left out of the file keeps its
+/// default from `consts.rs`. Also a resource.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub arena: Arena,
+    /// The size of the window along the arena's longest side.
+    pub window_size: f32,
+    /// Seconds between two moves of the snake.
+    pub move_delay: f64,
+    pub starting_len: usize,
+    /// Where the snake's head starts. Defaults to the middle of the top half of the arena, or as
+    /// far right of it as the snake's body needs.
+    pub start_pos: Option<Pos>,
+    pub colors: Colors,
+    pub sizes: Sizes,
+}
+
+/// Colours as RGB components between 0 and 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    pub background: [f32; 3],
+    pub snake_head: [f32; 3],
+    pub snake_tail: [f32; 3],
+    pub food: [f32; 3],
+}
+
+/// Sprite sizes, as a fraction of a cell.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sizes {
+    pub snake_head: f32,
+    pub snake_tail: f32,
+    pub food: f32,
+}
+
+impl Config {
+    /// Reads the config from `path`, or from [`DEFAULT_CONFIG_PATH`] if there is one, then applies
+    /// `overrides` and validates the result. Each override is a `key=value` pair, where the key
+    /// may be dotted to reach into a table, like `arena.width=30`, and the value is written as it
+    /// would be in the file.
+    pub fn load(path: Option<&str>, overrides: &[String]) -> Result<Self, String> {
+        let mut table = match path {
+            Some(path) => read_table(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => read_table(DEFAULT_CONFIG_PATH)?,
+            None => toml::value::Table::new(),
+        };
+        for entry in overrides {
+            set_override(&mut table, entry)?;
+        }
+        let config: Self = toml::Value::Table(table)
+            .try_into()
+            .map_err(|err| format!("Invalid config: {}", err))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that the settings make a playable game.
+    pub fn validate(&self) -> Result<(), String> {
+        let Arena { width, height } = self.arena;
+        if width < 2 || height < 2 {
+            return Err(format!(
+                "The arena must be at least 2x2, not {}x{}",
+                width, height
+            ));
+        }
+        if self.window_size <= 0.0 {
+            return Err("The window size must be positive".to_owned());
+        }
+        if self.move_delay <= 0.0 {
+            return Err("The move delay must be positive".to_owned());
+        }
+        // The snake needs a neck, which tells the AIs which way it is facing
+        if self.starting_len < 2 || self.starting_len >= self.arena.area() {
+            return Err(format!(
+                "The starting length must be between 2 and {}",
+                self.arena.area() - 1
+            ));
+        }
+        // The body trails straight back to the left of the head
+        if self.starting_len > width as usize {
+            return Err(format!(
+                "A snake of length {} doesn't fit in an arena {} cells wide",
+                self.starting_len, width
+            ));
+        }
+        let start = self.start_pos();
+        if !start.in_bounds(self.arena) {
+            return Err(format!(
+                "The start position ({}, {}) is outside the {}x{} arena",
+                start.x, start.y, width, height
+            ));
+        }
+        if (start.x as usize) + 1 < self.starting_len {
+            return Err(format!(
+                "A snake of length {} starting at ({}, {}) doesn't fit in the arena",
+                self.starting_len, start.x, start.y
+            ));
+        }
+        let Colors {
+            background,
+            snake_head,
+            snake_tail,
+            food,
+        } = self.colors;
+        let components = [background, snake_head, snake_tail, food]
+            .into_iter()
+            .flatten();
+        if components.into_iter().any(|c| !(0.0..=1.0).contains(&c)) {
+            return Err("Colour components must be between 0 and 1".to_owned());
+        }
+        let Sizes {
+            snake_head,
+            snake_tail,
+            food,
+        } = self.sizes;
+        if [snake_head, snake_tail, food]
+            .iter()
+            .any(|&size| size <= 0.0 || size > 1.0)
+        {
+            return Err("Sizes must be greater than 0 and at most 1".to_owned());
+        }
+        Ok(())
+    }
+
+    pub fn start_pos(&self) -> Pos {
+        let arena = self.arena;
+        // Far enough from the left edge for the body, in arenas too narrow to start in the middle
+        let x = (arena.width as usize / 2).max(self.starting_len.saturating_sub(1));
+        self.start_pos
+            .unwrap_or_else(|| Pos::new(x as i32, arena.height as i32 * 3 / 4))
+    }
+
+    /// Sets up a new game, with the snake facing right and no food placed.
+    pub fn new_game(&self) -> Simulation {
+        Simulation::new(self.arena, self.start_pos(), self.starting_len, Dir::Right)
+    }
+}
+
+/// Converts an RGB colour from the config to a Bevy colour.
+pub fn color([r, g, b]: [f32; 3]) -> Color {
+    Color::rgb(r, g, b)
+}
+
+fn read_table(path: &str) -> Result<toml::value::Table, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| format!("Couldn't read config file '{}': {}", path, err))?;
+    toml::from_str(&text).map_err(|err| format!("Invalid config file '{}': {}", path, err))
+}
+
+fn set_override(table: &mut toml::value::Table, entry: &str) -> Result<(), String> {
+    let (key, value) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid override '{}', expected KEY=VALUE", entry))?;
+    let value: toml::Value = toml::from_str::<toml::value::Table>(&format!("value = {}", value))
+        .map_err(|err| format!("Invalid value in override '{}': {}", entry, err))?
+        .remove("value")
+        .unwrap();
+    let mut keys: Vec<&str> = key.trim().split('.').collect();
+    let last = keys.pop().unwrap();
+    let mut table = table;
+    for key in keys {
+        table = table
+            .entry(key)
+            .or_insert_with(|| toml::Value::Table(Default::default()))
+            .as_table_mut()
+            .ok_or_else(|| format!("Invalid override '{}': '{}' is not a table", entry, key))?;
+    }
+    table.insert(last.to_owned(), value);
+    Ok(())
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            arena: Arena::default(),
+            window_size: WINDOW_SIZE,
+            move_delay: MOVE_DELAY,
+            starting_len: SNAKE_STARTING_LEN,
+            start_pos: None,
+            colors: Colors::default(),
+            sizes: Sizes::default(),
+        }
+    }
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        let rgb = |color: Color| {
+            let [r, g, b, _] = color.as_rgba_f32();
+            [r, g, b]
+        };
+        Self {
+            background: rgb(CLEAR_COLOR),
+            snake_head: rgb(SNAKE_HEAD_COLOR),
+            snake_tail: rgb(SNAKE_TAIL_COLOR),
+            food: rgb(FOOD_COLOR),
+        }
+    }
+}
+
+impl Default for Sizes {
+    fn default() -> Self {
+        Self {
+            snake_head: SNAKE_HEAD_SIZE.0,
+            snake_tail: SNAKE_TAIL_SEGMENT_SIZE.0,
+            food: FOOD_SIZE.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Config, String> {
+        let table = toml::from_str(text).unwrap();
+        let config: Config = toml::Value::Table(table)
+            .try_into()
+            .map_err(|err| err.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn missing_settings_keep_defaults() {
+        let config = parse("move_delay = 0.05\n[arena]\nwidth = 30\nheight = 10").unwrap();
+        assert_eq!(config.move_delay, 0.05);
+        assert_eq!(config.arena, Arena::new(30, 10));
+        assert_eq!(config.starting_len, SNAKE_STARTING_LEN);
+        assert_eq!(config.colors, Colors::default());
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        assert!(parse("move_dealy = 0.05").is_err());
+    }
+
+    #[test]
+    fn start_must_fit_in_arena() {
+        assert!(parse("start_pos = { x = 20, y = 5 }").is_err());
+        // The body trails off the left edge
+        assert!(parse("start_pos = { x = 2, y = 5 }").is_err());
+        assert!(parse("start_pos = { x = 3, y = 5 }").is_ok());
+        // Too narrow for the snake wherever it starts
+        assert!(parse("[arena]\nwidth = 3\nheight = 4").is_err());
+    }
+
+    #[test]
+    fn overrides_replace_settings() {
+        let mut table = toml::value::Table::new();
+        set_override(&mut table, "arena.width=30").unwrap();
+        set_override(&mut table, "move_delay = 0.5").unwrap();
+        let config: Config = toml::Value::Table(table).try_into().unwrap();
+        assert_eq!(config.arena, Arena::new(30, ARENA_HEIGHT));
+        assert_eq!(config.move_delay, 0.5);
+        assert!(set_override(&mut toml::value::Table::new(), "move_delay").is_err());
+    }
+
+    #[test]
+    fn default_game_fits_arena() {
+        for arena in [
+            Arena::new(4, 4),
+            Arena::new(5, 5),
+            Arena::new(6, 6),
+            Arena::new(100, 7),
+        ] {
+            let config = Config { arena, ..default() };
+            assert_eq!(config.validate(), Ok(()));
+            let sim = config.new_game();
+            assert!(sim.snake().iter().all(|pos| pos.in_bounds(arena)));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::components::{Size, *};
+use super::components::Size;
 use bevy::prelude::*;
 
 // Colors
@@ -6,17 +6,45 @@ pub const CLEAR_COLOR: Color = Color::BLACK;
 pub const SNAKE_HEAD_COLOR: Color = Color::rgb(0.7, 0.7, 0.7);
 pub const SNAKE_TAIL_COLOR: Color = SNAKE_HEAD_COLOR;
 pub const FOOD_COLOR: Color = Color::PURPLE;
+pub const HUD_COLOR: Color = Color::WHITE;
+pub const PATH_COLOR: Color = Color::rgba(1.0, 1.0, 0.0, 0.35);
+pub const HEATMAP_COLD_COLOR: Color = Color::rgba(0.0, 0.0, 1.0, 0.3);
+pub const HEATMAP_HOT_COLOR: Color = Color::rgba(1.0, 0.0, 0.0, 0.5);
 
 // Sizes
 pub const SNAKE_HEAD_SIZE: Size = Size(0.8);
 pub const SNAKE_TAIL_SEGMENT_SIZE: Size = Size(0.65);
+pub const FOOD_SIZE: Size = Size(0.8);
+pub const PATH_TILE_SIZE: Size = Size(0.5);
+pub const HUD_FONT_SIZE: f32 = 20.0;
+/// Distance between the HUD and the edges of the window, in pixels.
+pub const HUD_MARGIN: f32 = 8.0;
+/// How many of the best games the game over screen lists.
+pub const HIGH_SCORES_SHOWN: usize = 5;
 
 // Dimensions
 pub const ARENA_WIDTH: u32 = 20;
 pub const ARENA_HEIGHT: u32 = 20;
+/// The size of the window along the arena's longest side.
 pub const WINDOW_SIZE: f32 = 800.0;
 
 // Other
-pub const SNAKE_HEAD_START_POS: Pos = Pos::new(10, 15);
+/// Path of the HUD's font, relative to the `assets` directory.
+pub const HUD_FONT: &str = "fonts/DejaVuSans.ttf";
 pub const SNAKE_STARTING_LEN: usize = 4;
 pub const MOVE_DELAY: f64 = 0.15;
+/// How many turns pressed ahead of the snake's moves are remembered.
+pub const TURN_QUEUE_LEN: usize = 3;
+
+// Speed
+pub const MIN_MOVE_DELAY: f64 = 0.01;
+pub const MAX_MOVE_DELAY: f64 = 1.0;
+/// How much each press of `+` or `-` divides or multiplies the move delay by.
+pub const SPEED_FACTOR: f64 = 1.5;
+/// Moves per frame when turbo is turned on.
+pub const TURBO_MOVES: u32 = 10;
+pub const MAX_TURBO_MOVES: u32 = 256;
+
+// Rewind
+/// How many moves back the game can be rewound.
+pub const REWIND_MOVES: usize = 1000;
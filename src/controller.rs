@@ -0,0 +1,474 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::components::{Arena, Dir, Pos};
+use super::consts::TURN_QUEUE_LEN;
+use super::pathfinding::*;
+use super::simulation::Simulation;
+
+/// Decides which way the snake moves next.
+pub trait SnakeController: Send + Sync {
+    /// The name the controller is selected by.
+    fn name(&self) -> &'static str;
+
+    /// Chooses the direction of the snake's next move, given the current state of the game.
+    fn next_dir(&mut self, sim: &Simulation) -> Dir;
+
+    /// Whether the controller can play in `arena`.
+    fn supports(&self, _arena: Arena) -> bool {
+        true
+    }
+
+    /// Receives a direction key press. Only controllers steered by a human need it.
+    fn key_input(&mut self, _dir: Dir) {}
+
+    /// Called when a new game starts, to forget anything left over from the last one.
+    fn reset(&mut self) {}
+
+    /// The cells the controller planned to move the snake through when it chose the last move,
+    /// starting with the cell that move went to. Empty for controllers that don't plan ahead.
+    fn planned_path(&self) -> &[Pos] {
+        &[]
+    }
+
+    /// Whether the controller can record a [`SearchTrace`] of its searches.
+    fn can_trace(&self) -> bool {
+        false
+    }
+
+    /// Turns recording a [`SearchTrace`] of each search on or off. Controllers that can't trace
+    /// their searches ignore it.
+    fn trace_searches(&mut self, _enabled: bool) {}
+
+    /// The trace of the search made for the last move, while tracing is on.
+    fn search_trace(&self) -> Option<&SearchTrace> {
+        None
+    }
+}
+
+/// The controller that moves the snake, as a resource.
+pub struct Controller(pub Box<dyn SnakeController>);
+
+/// The names of all the controllers, as accepted by [`controller_by_name`].
+pub const CONTROLLER_NAMES: [&str; 6] = ["human", "greedy", "astar", "bfs", "safe", "hamilton"];
+
+pub fn controller_by_name(name: &str) -> Option<Box<dyn SnakeController>> {
+    let controller: Box<dyn SnakeController> = match name {
+        "human" => Box::new(HumanController::default()),
+        "greedy" => Box::new(GreedyController),
+        "astar" => Box::new(PathController::astar()),
+        "bfs" => Box::new(PathController::bfs()),
+        "safe" => Box::new(SafeController::default()),
+        "hamilton" => Box::new(HamiltonController::default()),
+        _ => return None,
+    };
+    Some(controller)
+}
+
+impl Default for Controller {
+    fn default() -> Self {
+        Self(Box::new(SafeController::default()))
+    }
+}
+
+/// Steered with the arrow keys. Keeps going straight until told otherwise. Turns are queued and
+/// made one per move, so that quick turns pressed between two moves aren't lost.
+#[derive(Default)]
+pub struct HumanController {
+    turns: VecDeque<Dir>,
+}
+
+impl SnakeController for HumanController {
+    fn name(&self) -> &'static str {
+        "human"
+    }
+
+    fn next_dir(&mut self, sim: &Simulation) -> Dir {
+        // The snake can't turn back on itself, so such a turn is skipped in favour of the next one
+        while let Some(dir) = self.turns.pop_front() {
+            if dir != sim.dir() && dir != sim.dir().opposite() {
+                return dir;
+            }
+        }
+        sim.dir()
+    }
+
+    fn key_input(&mut self, dir: Dir) {
+        if self.turns.len() < TURN_QUEUE_LEN && self.turns.back() != Some(&dir) {
+            self.turns.push_back(dir);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.turns.clear();
+    }
+}
+
+/// Always moves to whichever neighbouring cell is closest to the food, without looking any further
+/// ahead than that.
+pub struct GreedyController;
+
+impl SnakeController for GreedyController {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn next_dir(&mut self, sim: &Simulation) -> Dir {
+        let snake = snake(sim);
+        let food = match sim.food() {
+            Some(food) => food,
+            None => return fallback_move(sim.arena(), &snake, sim.dir()).unwrap_or(sim.dir()),
+        };
+        Dir::ALL
+            .into_iter()
+            .filter(|&dir| is_safe(sim.arena(), &snake, dir))
+            .min_by_key(|&dir| {
+                let pos = sim.head().in_direction(dir);
+                (pos.x - food.x).abs() + (pos.y - food.y).abs()
+            })
+            .unwrap_or(sim.dir())
+    }
+}
+
+/// A pathfinding function that also traces its search, like [`find_path_traced`].
+type TracedSearch = fn(Arena, Vec<Pos>, Pos) -> (PathResult, SearchTrace);
+
+/// Follows the shortest path to the food, as found by a pathfinding function.
+pub struct PathController {
+    name: &'static str,
+    find_path: fn(Arena, Vec<Pos>, Pos) -> PathResult,
+    /// The traced version of `find_path`, if there is one.
+    find_path_traced: Option<TracedSearch>,
+    tracing: bool,
+    trace: Option<SearchTrace>,
+    /// The cells of the last path found.
+    path: Vec<Pos>,
+}
+
+impl PathController {
+    pub fn astar() -> Self {
+        Self {
+            name: "astar",
+            find_path,
+            find_path_traced: Some(find_path_traced),
+            tracing: false,
+            trace: None,
+            path: Vec::new(),
+        }
+    }
+
+    pub fn bfs() -> Self {
+        Self {
+            name: "bfs",
+            find_path: find_path_bfs,
+            find_path_traced: None,
+            tracing: false,
+            trace: None,
+            path: Vec::new(),
+        }
+    }
+}
+
+impl SnakeController for PathController {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn next_dir(&mut self, sim: &Simulation) -> Dir {
+        let snake = snake(sim);
+        self.path.clear();
+        self.trace = None;
+        if let Some(food) = sim.food() {
+            let result = match self.find_path_traced {
+                Some(find_path_traced) if self.tracing => {
+                    let (result, trace) = find_path_traced(sim.arena(), snake.clone(), food);
+                    self.trace = Some(trace);
+                    result
+                }
+                _ => (self.find_path)(sim.arena(), snake.clone(), food),
+            };
+            if let PathResult::Found(path) = result {
+                self.path = path_cells(sim.head(), &path);
+                return path[0];
+            }
+        }
+        fallback_move(sim.arena(), &snake, sim.dir()).unwrap_or(sim.dir())
+    }
+
+    fn reset(&mut self) {
+        self.path.clear();
+        self.trace = None;
+    }
+
+    fn planned_path(&self) -> &[Pos] {
+        &self.path
+    }
+
+    fn can_trace(&self) -> bool {
+        self.find_path_traced.is_some()
+    }
+
+    fn trace_searches(&mut self, enabled: bool) {
+        self.tracing = enabled;
+        if !enabled {
+            self.trace = None;
+        }
+    }
+
+    fn search_trace(&self) -> Option<&SearchTrace> {
+        self.trace.as_ref()
+    }
+}
+
+/// Follows the shortest path to the food only when it doesn't lead into a trap. See
+/// [`find_safe_move`]. A path to the food is followed to the end once chosen, and the controller
+/// remembers where the snake has been since it last ate so that it doesn't go round in circles.
+#[derive(Default)]
+pub struct SafeController {
+    tracing: bool,
+    /// The trace of the search for the food.
+    trace: Option<SearchTrace>,
+    /// The cells of the last path chosen.
+    path: Vec<Pos>,
+    /// The rest of the path to the food. It was only found to be safe as a whole, and searching
+    /// again after each move might turn up a different one that isn't.
+    plan: Vec<Dir>,
+    /// How many times the snake has been in each position since it last ate.
+    seen: HashMap<Vec<Pos>, usize>,
+}
+
+impl SnakeController for SafeController {
+    fn name(&self) -> &'static str {
+        "safe"
+    }
+
+    fn next_dir(&mut self, sim: &Simulation) -> Dir {
+        let snake = snake(sim);
+        self.trace = None;
+        if sim.moves_since_food() == 0 {
+            self.seen.clear();
+        }
+        let visits = self.seen.entry(snake.clone()).or_default();
+        *visits += 1;
+        let visits = *visits;
+        let path = match sim.food() {
+            Some(_) if !self.plan.is_empty() => Some(self.plan.clone()),
+            // Back where it has been since it last ate, the snake would go round the same loop
+            // again, so it takes another of the ways out
+            Some(food) if visits > 1 => {
+                let away = moves_away(sim.arena(), &snake, food);
+                if away.is_empty() {
+                    fallback_move(sim.arena(), &snake, sim.dir()).map(|dir| vec![dir])
+                } else {
+                    Some(vec![away[(visits - 1) % away.len()]])
+                }
+            }
+            Some(food) if self.tracing => {
+                let (path, trace) = find_safe_path_traced(sim.arena(), snake, food, sim.dir());
+                self.trace = Some(trace);
+                path
+            }
+            Some(food) => find_safe_path(sim.arena(), snake, food, sim.dir()),
+            None => fallback_move(sim.arena(), &snake, sim.dir()).map(|dir| vec![dir]),
+        };
+        let path = path.unwrap_or_else(|| vec![sim.dir()]);
+        self.path = path_cells(sim.head(), &path);
+        self.plan.clear();
+        if self.path.last() == sim.food().as_ref() {
+            self.plan.extend(&path[1..]);
+        }
+        path[0]
+    }
+
+    fn reset(&mut self) {
+        self.path.clear();
+        self.plan.clear();
+        self.seen.clear();
+        self.trace = None;
+    }
+
+    fn planned_path(&self) -> &[Pos] {
+        &self.path
+    }
+
+    fn can_trace(&self) -> bool {
+        true
+    }
+
+    fn trace_searches(&mut self, enabled: bool) {
+        self.tracing = enabled;
+        if !enabled {
+            self.trace = None;
+        }
+    }
+
+    fn search_trace(&self) -> Option<&SearchTrace> {
+        self.trace.as_ref()
+    }
+}
+
+/// Follows a Hamiltonian cycle through the arena, taking shortcuts towards the food while it is
+/// safe to do so. Always fills the board. See [`HamiltonianCycle::next_move`].
+#[derive(Default)]
+pub struct HamiltonController {
+    cycle: Option<HamiltonianCycle>,
+}
+
+impl SnakeController for HamiltonController {
+    fn name(&self) -> &'static str {
+        "hamilton"
+    }
+
+    /// There is only a Hamiltonian cycle if one of the arena's sides is even.
+    fn supports(&self, arena: Arena) -> bool {
+        HamiltonianCycle::new(arena).is_some()
+    }
+
+    fn next_dir(&mut self, sim: &Simulation) -> Dir {
+        let snake = snake(sim);
+        let cycle = self.cycle.get_or_insert_with(|| {
+            let cycle = HamiltonianCycle::new(sim.arena())
+                .expect("there is no Hamiltonian cycle through an arena with odd dimensions");
+            // Travel the cycle the way round the body already lies along, which after a rewind
+            // or a switch from another controller may include shortcuts. Failing that, take
+            // whichever direction doesn't start by turning back on the snake.
+            let reversed = cycle.reversed();
+            if cycle.lies_along(&snake) {
+                cycle
+            } else if reversed.lies_along(&snake) || cycle.next(snake[0]) == snake[1] {
+                reversed
+            } else {
+                cycle
+            }
+        });
+        cycle
+            .next_move(&snake, sim.food())
+            .or_else(|| fallback_move(sim.arena(), &snake, sim.dir()))
+            .unwrap_or(sim.dir())
+    }
+
+    fn reset(&mut self) {
+        self.cycle = None;
+    }
+}
+
+fn snake(sim: &Simulation) -> Vec<Pos> {
+    sim.snake().iter().copied().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::GameRng;
+    use crate::config::Config;
+    use crate::simulation::StepOutcome;
+
+    #[test]
+    fn human_turns_are_queued() {
+        let mut sim = Config::default().new_game();
+        let mut controller = HumanController::default();
+        assert_eq!(sim.dir(), Dir::Right);
+        // Two quick turns between moves make a U-turn over two moves
+        controller.key_input(Dir::Up);
+        controller.key_input(Dir::Left);
+        sim.step(controller.next_dir(&sim));
+        assert_eq!(sim.dir(), Dir::Up);
+        sim.step(controller.next_dir(&sim));
+        assert_eq!(sim.dir(), Dir::Left);
+        sim.step(controller.next_dir(&sim));
+        assert_eq!(sim.dir(), Dir::Left);
+        // Turning back is skipped, but the turn after it still happens
+        controller.key_input(Dir::Right);
+        controller.key_input(Dir::Down);
+        sim.step(controller.next_dir(&sim));
+        assert_eq!(sim.dir(), Dir::Down);
+        // Only the first few turns are kept
+        for dir in [Dir::Left, Dir::Down, Dir::Right, Dir::Up, Dir::Left] {
+            controller.key_input(dir);
+        }
+        assert_eq!(controller.turns.len(), TURN_QUEUE_LEN);
+    }
+
+    #[test]
+    fn hamilton_fills_board() {
+        let config = Config {
+            arena: Arena::new(10, 8),
+            ..Default::default()
+        };
+        let mut sim = config.new_game();
+        let cells: Vec<Pos> = sim.arena().cells().collect();
+        let mut controller = HamiltonController::default();
+        while !sim.is_over() {
+            if sim.food().is_none() {
+                // Put the food on the last free cell, which tends to be the furthest away
+                let pos = cells.iter().rev().find(|pos| !sim.snake().contains(pos));
+                sim.place_food(*pos.unwrap());
+            }
+            let outcome = sim.step(controller.next_dir(&sim));
+            assert!(matches!(
+                outcome,
+                StepOutcome::Moved | StepOutcome::Ate | StepOutcome::Won
+            ));
+        }
+        assert!(sim.has_won());
+    }
+
+    #[test]
+    fn hamilton_fills_board_after_rewinding() {
+        let arena = Arena::new(10, 8);
+        let cycle = HamiltonianCycle::new(arena).unwrap();
+        // The cycle runs right along the bottom row, so these travel it one way and the other
+        for dir in [Dir::Right, Dir::Left] {
+            let sim = Simulation::new(arena, Pos::new(5, 0), 3, dir);
+            assert!(play_hamilton_rewinding(sim, &cycle) > 0);
+        }
+    }
+
+    /// Plays a game with the Hamilton controller, resetting it as a rewind does after every
+    /// shortcut, and checks the body keeps to the cycle. Returns the number of shortcuts taken.
+    fn play_hamilton_rewinding(mut sim: Simulation, cycle: &HamiltonianCycle) -> usize {
+        let reversed = cycle.reversed();
+        let mut rng = GameRng::new(1);
+        let mut controller = HamiltonController::default();
+        let mut shortcuts = 0;
+        sim.spawn_food(&mut rng.0);
+        while !sim.is_over() {
+            let outcome = sim.step(controller.next_dir(&sim));
+            assert!(matches!(
+                outcome,
+                StepOutcome::Moved | StepOutcome::Ate | StepOutcome::Won
+            ));
+            let snake = snake(&sim);
+            assert!(cycle.lies_along(&snake) || reversed.lies_along(&snake));
+            if outcome == StepOutcome::Ate {
+                sim.spawn_food(&mut rng.0);
+            }
+            // Rewinding to just after a shortcut leaves the controller to pick up from a neck
+            // that isn't the head's neighbour on the cycle
+            let skip = cycle.distance(sim.snake()[1], sim.head());
+            if skip != 1 && skip != sim.arena().area() - 1 {
+                shortcuts += 1;
+                controller.reset();
+            }
+        }
+        assert!(sim.has_won());
+        shortcuts
+    }
+
+    #[test]
+    fn path_controllers_plan_to_food() {
+        let food = Pos::new(2, 2);
+        for mut controller in [
+            controller_by_name("astar").unwrap(),
+            controller_by_name("safe").unwrap(),
+        ] {
+            let mut sim = Config::default().new_game();
+            sim.place_food(food);
+            let dir = controller.next_dir(&sim);
+            let path = controller.planned_path();
+            assert_eq!(path[0], sim.head().in_direction(dir));
+            assert_eq!(path.last(), Some(&food));
+            controller.reset();
+            assert!(controller.planned_path().is_empty());
+        }
+    }
+}
@@ -1,37 +1,72 @@
 use bevy::prelude::*;
-use rand::Rng;
 
 use super::components::*;
-use super::consts::*;
+use super::replay::Playback;
+use super::simulation::{Simulation, StepOutcome};
+use super::snake::{SnakeMoveTick, SnakeMovement};
 
 pub struct FoodPlugin;
 
+/// Label for the system that places new food once the snake has eaten.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FoodRespawn;
+
 impl Plugin for FoodPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(food_spawn).add_system(food_respawn);
+        app.add_system(
+            food_respawn
+                .label(FoodRespawn)
+                .after(SnakeMovement)
+                .with_run_criteria(SnakeMoveTick),
+        );
     }
 }
 
-fn food_respawn(commands: Commands, mut eat_event_reader: EventReader<EatEvent>) {
-    if eat_event_reader.iter().next().is_some() {
-        food_spawn(commands);
+fn food_respawn(
+    mut commands: Commands,
+    mut sim: ResMut<Simulation>,
+    mut rng: ResMut<GameRng>,
+    playback: Option<Res<Playback>>,
+    mut step_reader: EventReader<StepOutcome>,
+    mut food_query: Query<(Entity, &mut Pos), With<Food>>,
+) {
+    let ate = |outcome: &StepOutcome| matches!(outcome, StepOutcome::Ate | StepOutcome::Won);
+    if step_reader.iter().any(ate) {
+        let (food, mut food_pos) = food_query.single_mut();
+        match next_food(&mut sim, &mut rng, playback.as_deref()) {
+            Some(pos) => *food_pos = pos,
+            None => {
+                println!("There is no free cell left for the food");
+                commands.entity(food).despawn();
+            }
+        }
     }
 }
 
-fn food_spawn(mut commands: Commands) {
-    let mut rng = rand::thread_rng();
-    commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: FOOD_COLOR,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(Food)
-        .insert(Pos::new(
-            rng.gen_range(0..ARENA_WIDTH) as i32,
-            rng.gen_range(0..ARENA_HEIGHT) as i32,
-        ))
-        .insert(Size(0.8));
+/// Places the simulation's food and spawns an entity for it, unless the snake covers every cell.
+pub fn spawn_food(
+    commands: &mut Commands,
+    sim: &mut Simulation,
+    rng: &mut GameRng,
+    playback: Option<&Playback>,
+) {
+    if let Some(pos) = next_food(sim, rng, playback) {
+        commands.spawn().insert(Food).insert(pos);
+    }
+}
+
+/// Places the next food: where it was in the replay being played back, if the game is still
+/// following it, or else on a random free cell, like once a replay has been taken over or played
+/// past its end. Returns `None` if there is no free cell left.
+fn next_food(sim: &mut Simulation, rng: &mut GameRng, playback: Option<&Playback>) -> Option<Pos> {
+    let replayed = playback
+        .and_then(|playback| playback.food(sim))
+        .filter(|pos| !sim.snake().contains(pos));
+    match replayed {
+        Some(pos) => {
+            sim.place_food(pos);
+            Some(pos)
+        }
+        None => sim.spawn_food(&mut rng.0),
+    }
 }
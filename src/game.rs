@@ -0,0 +1,195 @@
+use bevy::{app::AppExit, prelude::*};
+
+use super::components::*;
+use super::config::Config;
+use super::controller::Controller;
+use super::food::spawn_food;
+use super::replay::Playback;
+use super::simulation::Simulation;
+use super::snake::spawn_snake;
+
+/// Sets up a new game whenever `Playing` is entered, and clears the old one away when restarting
+/// from the game over screen. With a window the game over screen waits for the restart key, without
+/// one the next game starts straight away.
+pub struct GamePlugin;
+
+/// Label for the system that sets up a new game when `Playing` is entered.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameStart;
+
+/// Counts the games played, and quits once the limit is reached, if there is one.
+#[derive(Default)]
+pub struct Games {
+    played: usize,
+    limit: Option<usize>,
+}
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        let headless = app.world.contains_resource::<Headless>();
+        for ended in GameState::ENDED {
+            let mut game_over = SystemSet::on_update(ended.clone());
+            if headless {
+                game_over = game_over.with_system(game_restart);
+            } else {
+                game_over = game_over.with_system(game_restart_input);
+                app.add_system_set(
+                    SystemSet::on_enter(ended.clone())
+                        .with_system(game_over_show.after(game_count)),
+                );
+            }
+            app.add_system_set(SystemSet::on_enter(ended).with_system(game_count))
+                .add_system_set(game_over);
+        }
+        app.init_resource::<Games>().add_system_set(
+            SystemSet::on_enter(GameState::Playing)
+                .with_system(game_reseed.before(GameStart))
+                .with_system(game_start.label(GameStart)),
+        );
+    }
+}
+
+/// Filter for the entities that make up a game, all of which are replaced when it restarts.
+pub type GameEntity = Or<(With<SnakeHead>, With<SnakeSegment>, With<Food>)>;
+
+/// Every game starts the random numbers afresh from its seed, so that it can be played again.
+fn game_reseed(seed: Res<GameSeed>, mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(seed.0);
+}
+
+fn game_start(
+    mut commands: Commands,
+    config: Res<Config>,
+    mut sim: ResMut<Simulation>,
+    mut body: ResMut<SnakeBody>,
+    mut controller: ResMut<Controller>,
+    mut rng: ResMut<GameRng>,
+    playback: Option<Res<Playback>>,
+) {
+    *sim = config.new_game();
+    *body = spawn_snake(&mut commands, &sim);
+    spawn_food(&mut commands, &mut sim, &mut rng, playback.as_deref());
+    controller.0.reset();
+}
+
+impl Games {
+    pub fn up_to(limit: Option<usize>) -> Self {
+        Self { played: 0, limit }
+    }
+
+    fn finished(&self) -> bool {
+        self.limit.is_some_and(|limit| self.played >= limit)
+    }
+}
+
+fn game_count(
+    sim: Res<Simulation>,
+    seed: Res<GameSeed>,
+    mut games: ResMut<Games>,
+    mut exit: EventWriter<AppExit>,
+) {
+    games.played += 1;
+    println!(
+        "Game {} with seed {}: length {} after {} moves",
+        games.played,
+        seed.0,
+        sim.len(),
+        sim.moves()
+    );
+    if games.finished() {
+        exit.send(AppExit);
+    }
+}
+
+fn game_over_show(games: Res<Games>) {
+    if games.finished() {
+        return;
+    }
+    println!("Press R to play again, or B to rewind");
+}
+
+fn game_restart(
+    mut commands: Commands,
+    games: Res<Games>,
+    mut seed: ResMut<GameSeed>,
+    entity_query: Query<Entity, GameEntity>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    // The app is on its way out, and another game would only be cut short
+    if games.finished() {
+        return;
+    }
+    restart(&mut commands, &mut seed, &entity_query, &mut game_state);
+}
+
+fn game_restart_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut commands: Commands,
+    mut seed: ResMut<GameSeed>,
+    entity_query: Query<Entity, GameEntity>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::R) {
+        restart(&mut commands, &mut seed, &entity_query, &mut game_state);
+    }
+}
+
+/// Clears the finished game away and starts the next one, with the next seed.
+fn restart(
+    commands: &mut Commands,
+    seed: &mut GameSeed,
+    entity_query: &Query<Entity, GameEntity>,
+    game_state: &mut State<GameState>,
+) {
+    for entity in entity_query {
+        commands.entity(entity).despawn();
+    }
+    seed.0 = seed.0.wrapping_add(1);
+    // Replaces the `Playing` state beneath `GameOver` or `Won` too, so that it is entered afresh
+    game_state.replace(GameState::Playing).unwrap();
+}
+
+/// Ends the game in the `ended` state, `GameOver` once the snake has died or `Won`. It goes on top
+/// of `Playing`, in place of `Paused` if the game was paused, so that a game rewound from the game
+/// over screen can be paused and carried on with.
+pub fn end_game(game_state: &mut State<GameState>, ended: GameState) {
+    match game_state.current() {
+        GameState::Paused => game_state.overwrite_set(ended),
+        _ => game_state.overwrite_push(ended),
+    }
+    .unwrap();
+}
+
+/// Replaces the snake and food entities with new ones mirroring `sim`, after it was changed other
+/// than by playing, like when rewinding.
+pub fn respawn_game(
+    commands: &mut Commands,
+    sim: &Simulation,
+    body: &mut SnakeBody,
+    entity_query: &Query<Entity, GameEntity>,
+) {
+    for entity in entity_query {
+        commands.entity(entity).despawn();
+    }
+    *body = spawn_snake(commands, sim);
+    if let Some(pos) = sim.food() {
+        commands.spawn().insert(Food).insert(pos);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn games_finished_past_limit() {
+        let mut games = Games::up_to(Some(1));
+        assert!(!games.finished());
+        games.played = 1;
+        assert!(games.finished());
+        // A game that ends while the app is on its way out still counts
+        games.played = 2;
+        assert!(games.finished());
+        assert!(!Games::up_to(None).finished());
+    }
+}
@@ -1,22 +1,33 @@
 use bevy::prelude::*;
 
 use super::components::{Pos, Size};
-use super::consts::*;
+use super::config::Config;
 
 pub struct GridTransformPlugin;
 
+/// Label for the systems that place entities on the screen from their grid position, in
+/// `PostUpdate`. Systems that draw on the grid from the game's state go in `PostUpdate` before
+/// them, so that they see every move made in `Update` and what they draw is placed the same frame.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GridTransform;
+
 impl Plugin for GridTransformPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set_to_stage(
             CoreStage::PostUpdate,
             SystemSet::new()
+                .label(GridTransform)
                 .with_system(position_translation)
                 .with_system(size_scaling),
         );
     }
 }
 
-fn size_scaling(windows: Res<Windows>, mut query: Query<(&Size, &mut Transform)>) {
+fn size_scaling(
+    windows: Res<Windows>,
+    config: Res<Config>,
+    mut query: Query<(&Size, &mut Transform)>,
+) {
     let window = match windows.get_primary() {
         Some(window) => window,
         None => return,
@@ -24,24 +35,26 @@ fn size_scaling(windows: Res<Windows>, mut query: Query<(&Size, &mut Transform)>
     for (sprite_size, mut transform) in &mut query {
         // Scale all transforms with Size components
         transform.scale = Vec3::new(
-            sprite_size.0 / ARENA_WIDTH as f32 * window.width() as f32,
-            sprite_size.0 / ARENA_HEIGHT as f32 * window.height() as f32,
+            sprite_size.0 / config.arena.width as f32 * window.width(),
+            sprite_size.0 / config.arena.height as f32 * window.height(),
             1.0,
         );
     }
 }
 
-fn position_translation(windows: Res<Windows>, mut query: Query<(&Pos, &mut Transform)>) {
+fn position_translation(
+    windows: Res<Windows>,
+    config: Res<Config>,
+    mut query: Query<(&Pos, &mut Transform)>,
+) {
     let window = match windows.get_primary() {
         Some(window) => window,
         None => return,
     };
+    // The depth is left alone, so that entities can be drawn in front of one another
     for (pos, mut xform) in &mut query {
-        xform.translation = Vec3::new(
-            convert(pos.x, window.width(), ARENA_WIDTH),
-            convert(pos.y, window.height(), ARENA_HEIGHT),
-            0.0,
-        );
+        xform.translation.x = convert(pos.x, window.width(), config.arena.width);
+        xform.translation.y = convert(pos.y, window.height(), config.arena.height);
     }
     /// Convert "arena" units to "window" units, move the "arena" origin (0, 0) to the bottom left of
     /// the window, and move the "tile" origin from the center to the bottom left of the tile.
@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use super::config::Config;
+use super::consts::*;
+use super::controller::Controller;
+use super::grid_transform::GridTransform;
+use super::pathfinding::CellTrace;
+
+/// Colours the cells by what the AI's last A* search did with them, to help tune the search. `H`
+/// cycles through the f, g and h costs, the number of expansions, and off, for the AIs that can
+/// trace their searches. See [`SearchTrace`](super::pathfinding::SearchTrace).
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Heatmap>()
+            .add_startup_system(heatmap_setup)
+            .add_system(heatmap_input)
+            .add_system_to_stage(CoreStage::PostUpdate, heatmap_update.before(GridTransform));
+    }
+}
+
+/// What the heatmap shows, if anything.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Heatmap {
+    #[default]
+    Off,
+    FCost,
+    GCost,
+    HCost,
+    Expansions,
+}
+
+/// A tile of the heatmap. There is one for every cell, hidden unless the search reached it.
+#[derive(Component)]
+struct HeatmapTile;
+
+/// The depth of the heatmap tiles, in front of the snake and the food but behind the path overlay.
+const HEATMAP_TILE_Z: f32 = 0.5;
+
+impl Heatmap {
+    fn next(self) -> Self {
+        match self {
+            Heatmap::Off => Heatmap::FCost,
+            Heatmap::FCost => Heatmap::GCost,
+            Heatmap::GCost => Heatmap::HCost,
+            Heatmap::HCost => Heatmap::Expansions,
+            Heatmap::Expansions => Heatmap::Off,
+        }
+    }
+
+    /// The value shown for a cell, or `None` if the search never reached it.
+    fn value(self, cell: CellTrace) -> Option<u32> {
+        let cost = cell.cost?;
+        match self {
+            Heatmap::Off => None,
+            Heatmap::FCost => Some(cost.f_cost),
+            Heatmap::GCost => Some(cost.g_cost),
+            Heatmap::HCost => Some(cost.h_cost),
+            Heatmap::Expansions => Some(cell.expansions),
+        }
+    }
+}
+
+fn heatmap_setup(mut commands: Commands, config: Res<Config>) {
+    for pos in config.arena.cells() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform::from_xyz(0.0, 0.0, HEATMAP_TILE_Z),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            })
+            .insert(HeatmapTile)
+            .insert(pos)
+            .insert(Size(1.0));
+    }
+}
+
+fn heatmap_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut heatmap: ResMut<Heatmap>,
+    mut controller: ResMut<Controller>,
+) {
+    if keyboard_input.just_pressed(KeyCode::H) {
+        // It can still be turned off after switching to such an AI
+        if *heatmap == Heatmap::Off && !controller.0.can_trace() {
+            println!("{}", no_heatmap(&controller));
+            return;
+        }
+        *heatmap = heatmap.next();
+        controller.0.trace_searches(*heatmap != Heatmap::Off);
+        println!("Heatmap: {:?}", *heatmap);
+    }
+}
+
+/// Explains why there is no heatmap for `controller`.
+pub fn no_heatmap(controller: &Controller) -> String {
+    format!(
+        "There is no heatmap for the {} AI, which doesn't trace its searches",
+        controller.0.name()
+    )
+}
+
+fn heatmap_update(
+    heatmap: Res<Heatmap>,
+    controller: Res<Controller>,
+    mut tile_query: Query<(&Pos, &mut Sprite, &mut Visibility), With<HeatmapTile>>,
+) {
+    let trace = match controller.0.search_trace() {
+        Some(trace) if *heatmap != Heatmap::Off => trace,
+        _ => {
+            for (_, _, mut visibility) in &mut tile_query {
+                visibility.is_visible = false;
+            }
+            return;
+        }
+    };
+    let max = trace
+        .arena()
+        .cells()
+        .filter_map(|pos| heatmap.value(trace.get(pos)))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for (&pos, mut sprite, mut visibility) in &mut tile_query {
+        match heatmap.value(trace.get(pos)) {
+            Some(value) => {
+                sprite.color = heat_color(value as f32 / max as f32);
+                visibility.is_visible = true;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+}
+
+/// Blends from the cold colour at 0 to the hot colour at 1.
+fn heat_color(heat: f32) -> Color {
+    let [r1, g1, b1, a1] = HEATMAP_COLD_COLOR.as_rgba_f32();
+    let [r2, g2, b2, a2] = HEATMAP_HOT_COLOR.as_rgba_f32();
+    let blend = |cold: f32, hot: f32| cold + (hot - cold) * heat;
+    Color::rgba(blend(r1, r2), blend(g1, g2), blend(b1, b2), blend(a1, a2))
+}
@@ -0,0 +1,141 @@
+use bevy::{prelude::*, time::Stopwatch};
+
+use super::components::*;
+use super::consts::*;
+use super::controller::Controller;
+use super::scores::HighScores;
+use super::simulation::Simulation;
+use super::speed::Speed;
+use super::steering::{Steering, SteeringMode};
+
+/// Shows the state of the game in the top left corner of the window: the snake's length and score,
+/// the moves taken, how long the game has lasted, who is steering and what to do next. Once the game
+/// is over, it also lists the high scores for the AI and arena size.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .add_startup_system(hud_setup)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(clock_reset))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(clock_tick))
+            // The text is rebuilt once a frame, rather than for each of the frame's moves
+            .add_system_to_stage(CoreStage::PostUpdate, hud_update)
+            .add_system_to_stage(CoreStage::PostUpdate, hud_scores);
+    }
+}
+
+/// The HUD's text, with the state of the game in its first section and the high scores in its
+/// second.
+#[derive(Component)]
+struct Hud;
+
+/// The time spent playing the current game, not counting pauses.
+#[derive(Default, Deref, DerefMut)]
+struct GameClock(Stopwatch);
+
+fn hud_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let style = TextStyle {
+        font: asset_server.load(HUD_FONT),
+        font_size: HUD_FONT_SIZE,
+        color: HUD_COLOR,
+    };
+    commands
+        .spawn_bundle(
+            TextBundle::from_sections([
+                TextSection::new("", style.clone()),
+                TextSection::new("", style),
+            ])
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(HUD_MARGIN),
+                    left: Val::Px(HUD_MARGIN),
+                    ..default()
+                },
+                ..default()
+            }),
+        )
+        .insert(Hud);
+}
+
+fn clock_reset(mut clock: ResMut<GameClock>) {
+    clock.reset();
+}
+
+fn clock_tick(time: Res<Time>, mut clock: ResMut<GameClock>) {
+    clock.tick(time.delta());
+}
+
+fn hud_update(
+    sim: Res<Simulation>,
+    controller: Res<Controller>,
+    steering: Res<Steering>,
+    speed: Res<Speed>,
+    clock: Res<GameClock>,
+    game_state: Res<State<GameState>>,
+    mut hud_query: Query<&mut Text, With<Hud>>,
+) {
+    let status = match game_state.current() {
+        GameState::Menu => "Press Space to start".to_owned(),
+        GameState::Playing => format!("Speed: {}", *speed),
+        GameState::Paused => {
+            "Paused, press P to resume, N to step, B to step back or C to switch AI".to_owned()
+        }
+        GameState::Won => "You win! Press R to play again or B to rewind".to_owned(),
+        GameState::GameOver => "Game over! Press R to play again or B to rewind".to_owned(),
+    };
+    let player = match steering.mode {
+        SteeringMode::Ai => format!("AI: {}", controller.0.name()),
+        SteeringMode::Assist => format!("You, assisted by {}", controller.0.name()),
+        SteeringMode::Human => "You".to_owned(),
+    };
+    // The snake grows by one for every food, so the score is simply the food eaten
+    let text = format!(
+        "Length: {}\nScore: {}\nMoves: {} ({} since food)\nTime: {:.1}s\n{}\n{}",
+        sim.len(),
+        sim.eaten(),
+        sim.moves(),
+        sim.moves_since_food(),
+        clock.elapsed_secs(),
+        player,
+        status
+    );
+    let mut hud = hud_query.single_mut();
+    if hud.sections[0].value != text {
+        hud.sections[0].value = text;
+    }
+}
+
+/// Lists the high scores while the game is over.
+fn hud_scores(
+    sim: Res<Simulation>,
+    controller: Res<Controller>,
+    seed: Res<GameSeed>,
+    scores: Res<HighScores>,
+    game_state: Res<State<GameState>>,
+    mut hud_query: Query<&mut Text, With<Hud>>,
+) {
+    if !game_state.is_changed() && !scores.is_changed() {
+        return;
+    }
+    let mut text = String::new();
+    if game_state.current().is_ended() {
+        let ai = controller.0.name();
+        let arena = sim.arena();
+        text = format!(
+            "\n\nHigh scores, {} on {}x{}:",
+            ai, arena.width, arena.height
+        );
+        for score in scores.table(ai, arena).iter().take(HIGH_SCORES_SHOWN) {
+            text += &format!(
+                "\n{} in {} moves, seed {}",
+                score.len, score.moves, score.seed
+            );
+            if score.seed == seed.0 {
+                text += " (this seed)";
+            }
+        }
+    }
+    hud_query.single_mut().sections[1].value = text;
+}
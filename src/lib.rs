@@ -0,0 +1,24 @@
+pub mod bench;
+pub mod cli;
+pub mod collision;
+pub mod components;
+pub mod config;
+pub mod consts;
+pub mod controller;
+pub mod food;
+pub mod game;
+pub mod grid_transform;
+pub mod heatmap;
+pub mod hud;
+pub mod menu;
+pub mod overlay;
+pub mod pathfinding;
+pub mod pause;
+pub mod replay;
+pub mod rewind;
+pub mod scores;
+pub mod simulation;
+pub mod snake;
+pub mod speed;
+pub mod sprites;
+pub mod steering;
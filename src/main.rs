@@ -1,38 +1,154 @@
 use bevy::{prelude::*, window::close_on_esc};
 
-mod collision;
-mod components;
-mod consts;
-mod food;
-mod grid_transform;
-mod pathfinding;
-mod snake;
-
-use collision::CollisionPlugin;
-use components::GameState;
-use consts::*;
-use food::FoodPlugin;
-use grid_transform::GridTransformPlugin;
-use snake::SnakePlugin;
+use bevy_ai_snake::cli::{Args, USAGE};
+use bevy_ai_snake::collision::CollisionPlugin;
+use bevy_ai_snake::components::{GameRng, GameSeed, GameState, Headless};
+use bevy_ai_snake::config::{color, Config};
+use bevy_ai_snake::controller::{controller_by_name, Controller};
+use bevy_ai_snake::food::FoodPlugin;
+use bevy_ai_snake::game::{GamePlugin, Games};
+use bevy_ai_snake::grid_transform::GridTransformPlugin;
+use bevy_ai_snake::heatmap::HeatmapPlugin;
+use bevy_ai_snake::hud::HudPlugin;
+use bevy_ai_snake::menu::MenuPlugin;
+use bevy_ai_snake::overlay::PathOverlayPlugin;
+use bevy_ai_snake::pause::PausePlugin;
+use bevy_ai_snake::replay::{Playback, PlaybackPlugin, RecordPlugin, Replay, ReplayController};
+use bevy_ai_snake::rewind::RewindPlugin;
+use bevy_ai_snake::scores::{HighScores, ScoresPlugin, SCORES_PATH};
+use bevy_ai_snake::snake::SnakePlugin;
+use bevy_ai_snake::speed::SpeedPlugin;
+use bevy_ai_snake::sprites::SpritesPlugin;
+use bevy_ai_snake::steering::SteeringPlugin;
 
 fn main() {
-    App::new()
-        .insert_resource(WindowDescriptor {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(1);
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        return;
+    }
+    let mut config = match Config::load(args.config_path.as_deref(), &args.overrides) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    // A damaged scores file is no reason not to play, but it is left alone for the player to fix
+    let scores = match HighScores::load(SCORES_PATH) {
+        Ok(scores) => scores,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!(
+                "Starting without high scores. They won't be saved, so as not to overwrite '{}'",
+                SCORES_PATH
+            );
+            HighScores::default()
+        }
+    };
+    let playback = args
+        .replay_path
+        .as_deref()
+        .map(|path| match Replay::load(path) {
+            Ok(replay) => replay,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        });
+    if let Some(replay) = &playback {
+        if args.ai.is_some() || args.seed.is_some() {
+            eprintln!("A replay is always played back with its own moves and food");
+            std::process::exit(1);
+        }
+        // The game itself is set up as it was recorded, but the speed and looks are still up to
+        // the config
+        config.arena = replay.config.arena;
+        config.starting_len = replay.config.starting_len;
+        config.start_pos = replay.config.start_pos;
+        println!("Replaying a game played by {}", replay.ai);
+    }
+    let controller = match (&playback, args.ai) {
+        (Some(replay), _) => Controller(Box::new(ReplayController::new(replay))),
+        (None, Some(name)) => Controller(controller_by_name(name).unwrap()),
+        (None, None) => Controller::default(),
+    };
+    let arena = config.arena;
+    if !controller.0.supports(arena) {
+        eprintln!(
+            "The {} AI can't play in a {}x{} arena",
+            controller.0.name(),
+            arena.width,
+            arena.height
+        );
+        std::process::exit(1);
+    }
+
+    // Without a seed, pick one at random and print it, so that the game can still be replayed
+    let seed = match &playback {
+        Some(replay) => replay.seed,
+        None => args.seed.unwrap_or_else(rand::random),
+    };
+    println!("AI: {}", controller.0.name());
+    println!("Seed: {}", seed);
+
+    let mut app = App::new();
+    app.insert_resource(controller)
+        .insert_resource(GameSeed(seed))
+        .insert_resource(GameRng::new(seed))
+        .insert_resource(scores)
+        .insert_resource(config.clone())
+        .insert_resource(Games::up_to(args.games));
+    if args.headless {
+        // Nobody is there to press start, so skip the menu
+        app.insert_resource(Headless)
+            .add_state(GameState::Playing)
+            .add_plugins(MinimalPlugins);
+    } else {
+        // Keep the cells square, with the longest side of the arena filling the window size
+        let cell_size = config.window_size / arena.width.max(arena.height) as f32;
+        app.insert_resource(WindowDescriptor {
             title: "AI Snake!".to_owned(),
-            width: WINDOW_SIZE,
-            height: WINDOW_SIZE,
+            width: cell_size * arena.width as f32,
+            height: cell_size * arena.height as f32,
             resizable: false,
             ..default()
         })
-        .insert_resource(ClearColor(CLEAR_COLOR))
-        .insert_resource(GameState::Playing)
+        .insert_resource(ClearColor(color(config.colors.background)))
+        .add_state(GameState::Menu)
         .add_startup_system(setup_camera)
         .add_system(close_on_esc)
-        .add_plugin(FoodPlugin)
+        .add_plugin(GridTransformPlugin)
+        .add_plugin(SpritesPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(PausePlugin)
+        .add_plugin(SpeedPlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(PathOverlayPlugin)
+        .add_plugin(HeatmapPlugin)
+        .add_plugin(RewindPlugin)
+        .add_plugin(SteeringPlugin)
+        .add_plugins(DefaultPlugins);
+    }
+    match playback {
+        Some(replay) => {
+            app.insert_resource(Playback::new(replay))
+                .add_plugin(PlaybackPlugin);
+        }
+        None => {
+            app.add_plugin(RecordPlugin).add_plugin(ScoresPlugin);
+        }
+    }
+    app.add_plugin(FoodPlugin)
         .add_plugin(SnakePlugin)
         .add_plugin(CollisionPlugin)
-        .add_plugin(GridTransformPlugin)
-        .add_plugins(DefaultPlugins)
+        .add_plugin(GamePlugin)
         .run();
 }
 
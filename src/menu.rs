@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+use super::components::*;
+
+/// The screen shown before the first game, which waits for `Space` to start playing.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(menu_show))
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(menu_input));
+    }
+}
+
+fn menu_show() {
+    println!("Press Space to start");
+}
+
+fn menu_input(keyboard_input: Res<Input<KeyCode>>, mut game_state: ResMut<State<GameState>>) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        game_state.set(GameState::Playing).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use super::consts::*;
+use super::controller::Controller;
+use super::grid_transform::GridTransform;
+use super::simulation::Simulation;
+
+/// Draws the path the AI is following from the snake's head to the food, toggled with `O`. See
+/// [`SnakeController::planned_path`](super::controller::SnakeController::planned_path).
+pub struct PathOverlayPlugin;
+
+impl Plugin for PathOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PathOverlay>()
+            .add_system(path_overlay_input)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                path_overlay_update.before(GridTransform),
+            );
+    }
+}
+
+/// Whether the path overlay is shown.
+#[derive(Default)]
+pub struct PathOverlay {
+    pub shown: bool,
+}
+
+/// A tile of the path overlay. Tiles are kept around and reused as the path changes, and hidden
+/// when there are more of them than cells in the path.
+#[derive(Component)]
+struct PathTile;
+
+/// The depth of the path tiles, in front of the snake and the food.
+const PATH_TILE_Z: f32 = 1.0;
+
+fn path_overlay_input(keyboard_input: Res<Input<KeyCode>>, mut overlay: ResMut<PathOverlay>) {
+    if keyboard_input.just_pressed(KeyCode::O) {
+        overlay.shown = !overlay.shown;
+        println!("Path overlay {}", if overlay.shown { "on" } else { "off" });
+    }
+}
+
+fn path_overlay_update(
+    mut commands: Commands,
+    overlay: Res<PathOverlay>,
+    sim: Res<Simulation>,
+    controller: Res<Controller>,
+    mut tile_query: Query<(&mut Pos, &mut Visibility), With<PathTile>>,
+) {
+    let mut cells: Vec<Pos> = Vec::new();
+    if overlay.shown {
+        // The path starts where the head is now, and ends on the food
+        cells = controller
+            .0
+            .planned_path()
+            .iter()
+            .copied()
+            .filter(|pos| !sim.snake().contains(pos) && Some(*pos) != sim.food())
+            .collect();
+    }
+    let mut cells = cells.into_iter();
+    for (mut pos, mut visibility) in &mut tile_query {
+        match cells.next() {
+            Some(cell) => {
+                *pos = cell;
+                visibility.is_visible = true;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+    // The path is longer than it has ever been. New tiles are hidden until the next frame, by
+    // which time they have been placed on screen.
+    for cell in cells {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: PATH_COLOR,
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, PATH_TILE_Z),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            })
+            .insert(PathTile)
+            .insert(cell)
+            .insert(PATH_TILE_SIZE);
+    }
+}
@@ -1,11 +1,7 @@
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
 
 use super::components::*;
-use super::consts::*;
-
-const BOARD_SIZE: usize = ARENA_WIDTH as usize * ARENA_HEIGHT as usize;
-const BOARD_MAX_DIST: usize = ARENA_WIDTH as usize + ARENA_HEIGHT as usize;
 
 #[derive(Debug, Copy, Clone, Eq)]
 struct HeapElem {
@@ -20,31 +16,99 @@ struct Node {
     dir_back: Option<Dir>,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 enum NodeKind {
     Food,
+    #[default]
     Empty,
     Snake,
 }
 
+/// The A* costs of a cell.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-struct Cost {
-    f_cost: u32,
-    g_cost: u32,
-    h_cost: u32,
+pub struct Cost {
+    /// The estimated length of the whole path through the cell, `g_cost + h_cost`.
+    pub f_cost: u32,
+    /// The length of the path from the snake head to the cell.
+    pub g_cost: u32,
+    /// The distance left from the cell to the goal, ignoring obstacles.
+    pub h_cost: u32,
 }
 
 struct Board {
-    nodes: [Node; BOARD_SIZE],
+    arena: Arena,
+    /// Indexed by [`Arena::index`].
+    nodes: Vec<Node>,
+}
+
+/// A route through every cell of the arena that ends where it started. A snake that follows it
+/// can never run into itself, no matter how long it grows.
+#[derive(Debug, Clone)]
+pub struct HamiltonianCycle {
+    arena: Arena,
+    /// The cells in the order they are visited.
+    cells: Vec<Pos>,
+    /// The position of each cell within `cells`, indexed by [`Arena::index`].
+    order: Vec<usize>,
+}
+
+/// A record of an A* search made by [`find_path_traced`] or [`find_safe_path_traced`], for
+/// debugging.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SearchTrace {
+    arena: Arena,
+    /// Indexed by [`Arena::index`].
+    cells: Vec<CellTrace>,
+}
+
+/// What an A* search did with a cell.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct CellTrace {
+    /// The costs of the shortest path to the cell that was found, if the search reached it.
+    pub cost: Option<Cost>,
+    /// How many times the cell was taken off the queue to look at its neighbours.
+    pub expansions: u32,
+}
+
+/// The result of searching for a path from the snake head.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PathResult {
+    /// The shortest path to the goal, starting with the first move the snake head should make.
+    Found(Vec<Dir>),
+    /// The goal is cut off from the snake head, but the snake can still move somewhere.
+    Unreachable,
+    /// Every move from the snake head runs into a wall or the snake's body.
+    NoSafeMove,
 }
 
 /// Uses A* pathfinding to find the shortest path from the snake head to `food`. The snake head is
 /// assumed to be the first element of `snake`.
-pub fn find_path(snake: Vec<Pos>, food: Pos) -> Vec<Dir> {
+pub fn find_path(arena: Arena, snake: Vec<Pos>, food: Pos) -> PathResult {
+    astar(arena, snake, food, None)
+}
+
+/// Like [`find_path`], but also returns a trace of the costs given to each cell and how often each
+/// was expanded.
+pub fn find_path_traced(arena: Arena, snake: Vec<Pos>, food: Pos) -> (PathResult, SearchTrace) {
+    let mut trace = SearchTrace::new(arena);
+    let result = astar(arena, snake, food, Some(&mut trace));
+    (result, trace)
+}
+
+fn astar(
+    arena: Arena,
+    snake: Vec<Pos>,
+    food: Pos,
+    mut trace: Option<&mut SearchTrace>,
+) -> PathResult {
     let head = snake[0];
-    let mut board = Board::new(snake, food);
+    let has_safe_move = Dir::ALL.into_iter().any(|dir| is_safe(arena, &snake, dir));
+    let mut board = Board::new(arena, snake, food);
     // Compute the H cost from the head to the food and store it in `board`. G cost starts at 0.
     board.get_mut(head).cost = Cost::compute(0, head, food);
+    if let Some(trace) = &mut trace {
+        trace.get_mut(head).cost = Some(board.get(head).cost);
+    }
     // Create a min-heap (priority queue) containing only the snake head node.
     let mut heap = BinaryHeap::new();
     heap.push(HeapElem {
@@ -53,11 +117,14 @@ pub fn find_path(snake: Vec<Pos>, food: Pos) -> Vec<Dir> {
     });
     // Keep searching until the heap is empty, lowest-cost nodes first.
     while let Some(HeapElem { node, pos }) = heap.pop() {
+        if let Some(trace) = &mut trace {
+            trace.get_mut(pos).expansions += 1;
+        }
         // Look in each direction
-        for dir in [Dir::Left, Dir::Right, Dir::Up, Dir::Down] {
+        for dir in Dir::ALL {
             let next_pos = pos.in_direction(dir);
             // Skip the node if it is out of bounds
-            if !next_pos.in_bounds() {
+            if !next_pos.in_bounds(arena) {
                 continue;
             }
             let next_node = board.get_mut(next_pos);
@@ -71,22 +138,16 @@ pub fn find_path(snake: Vec<Pos>, food: Pos) -> Vec<Dir> {
             }
             // Update the direction back to the snake head
             next_node.dir_back = Some(dir.opposite());
+            // Update the node's cost
+            next_node.cost = Cost::compute(node.cost.g_cost + 1, next_pos, food);
+            if let Some(trace) = &mut trace {
+                trace.get_mut(next_pos).cost = Some(next_node.cost);
+            }
             // Check if we have found the food yet
             if next_node.kind == NodeKind::Food {
                 // Path found!
-                // Trace the path back to the snake head and record the directions needed to get
-                // there, in reverse, so that the final result is a path from the snake head to the
-                // food.
-                let mut path = Vec::with_capacity(BOARD_MAX_DIST);
-                let mut pos = next_pos;
-                while let Some(dir) = board.get(pos).dir_back {
-                    path.push(dir.opposite());
-                    pos = pos.in_direction(dir);
-                }
-                return path;
+                return PathResult::Found(board.trace_path(next_pos));
             }
-            // Update the node's cost
-            next_node.cost = Cost::compute(node.cost.g_cost + 1, next_pos, food);
             // Add it to the queue
             heap.push(HeapElem {
                 node: *next_node,
@@ -95,27 +156,416 @@ pub fn find_path(snake: Vec<Pos>, food: Pos) -> Vec<Dir> {
         }
     }
 
-    vec![]
+    if has_safe_move {
+        PathResult::Unreachable
+    } else {
+        PathResult::NoSafeMove
+    }
+}
+
+/// Uses breadth-first search to find the shortest path from the snake head to `food`. Finds paths
+/// just as short as [`find_path`], but explores the board evenly in every direction rather than
+/// heading straight for the food.
+pub fn find_path_bfs(arena: Arena, snake: Vec<Pos>, food: Pos) -> PathResult {
+    let head = snake[0];
+    let has_safe_move = Dir::ALL.into_iter().any(|dir| is_safe(arena, &snake, dir));
+    let mut board = Board::new(arena, snake, food);
+    let mut queue = VecDeque::from([head]);
+    while let Some(pos) = queue.pop_front() {
+        for dir in Dir::ALL {
+            let next_pos = pos.in_direction(dir);
+            if !next_pos.in_bounds(arena) {
+                continue;
+            }
+            let next_node = board.get_mut(next_pos);
+            // Skip the node if it is part of the snake's body or has already been visited
+            if next_node.kind == NodeKind::Snake || next_node.dir_back.is_some() {
+                continue;
+            }
+            next_node.dir_back = Some(dir.opposite());
+            if next_node.kind == NodeKind::Food {
+                return PathResult::Found(board.trace_path(next_pos));
+            }
+            queue.push_back(next_pos);
+        }
+    }
+
+    if has_safe_move {
+        PathResult::Unreachable
+    } else {
+        PathResult::NoSafeMove
+    }
+}
+
+/// Chooses a move towards `food` that does not leave the snake trapped. The shortest path to the
+/// food is only taken if, once the snake has followed it and eaten, the head can still reach the
+/// tail. Otherwise the snake takes the move furthest from the food that still leaves it a way out,
+/// so that it doesn't circle the same loop while its body moves out of the way, and failing that
+/// it falls back to [`fallback_move`]. Returns `None` if every move is fatal.
+pub fn find_safe_move(arena: Arena, snake: Vec<Pos>, food: Pos, dir: Dir) -> Option<Dir> {
+    find_safe_path(arena, snake, food, dir).map(|path| path[0])
+}
+
+/// Like [`find_safe_move`], but returns the whole path that the move starts: to the food, or just
+/// the single move away from it.
+pub fn find_safe_path(arena: Arena, snake: Vec<Pos>, food: Pos, dir: Dir) -> Option<Vec<Dir>> {
+    safe_path(arena, snake, food, dir, None)
+}
+
+/// Like [`find_safe_path`], but also returns a trace of the search for a path to the food.
+pub fn find_safe_path_traced(
+    arena: Arena,
+    snake: Vec<Pos>,
+    food: Pos,
+    dir: Dir,
+) -> (Option<Vec<Dir>>, SearchTrace) {
+    let mut trace = SearchTrace::new(arena);
+    let path = safe_path(arena, snake, food, dir, Some(&mut trace));
+    (path, trace)
+}
+
+fn safe_path(
+    arena: Arena,
+    snake: Vec<Pos>,
+    food: Pos,
+    dir: Dir,
+    trace: Option<&mut SearchTrace>,
+) -> Option<Vec<Dir>> {
+    // Besides the shortest path, try one that runs into the body where the tail will have moved on
+    // by the time the head gets there, which may leave the snake better placed
+    let shortest = match astar(arena, snake.clone(), food, trace) {
+        PathResult::Found(path) => Some(path),
+        _ => None,
+    };
+    let through_body = moving_body_search(arena, &snake, None, 0, |pos| pos == food);
+    for path in shortest.into_iter().chain(through_body) {
+        // Eating the last food wins, whether or not there is a way out afterwards
+        let fed = follow_path(&snake, &path, food);
+        if fed.len() == arena.area() || can_escape(arena, &fed, None, 1) {
+            return Some(path);
+        }
+    }
+    // Otherwise keep away from the food, which the snake can't safely eat yet. Following the
+    // shortest way to the tail instead would lead it round the same loop for as long as the food
+    // stays out of reach
+    let away = moves_away(arena, &snake, food).first().copied();
+    away.or_else(|| fallback_move(arena, &snake, dir))
+        .map(|dir| vec![dir])
+}
+
+/// The moves that leave the snake a way out as in [`can_escape`], furthest from `food` first.
+pub fn moves_away(arena: Arena, snake: &[Pos], food: Pos) -> Vec<Dir> {
+    let mut moves: Vec<Dir> = Dir::ALL
+        .into_iter()
+        .filter(|&dir| is_safe(arena, snake, dir))
+        .filter(|&dir| can_escape_after(arena, snake, dir, food))
+        .collect();
+    moves.sort_by_key(|&dir| Reverse(distance(snake[0].in_direction(dir), food)));
+    moves
+}
+
+/// The cells visited by following `path` from `start`, not including `start`.
+pub fn path_cells(start: Pos, path: &[Dir]) -> Vec<Pos> {
+    path.iter()
+        .scan(start, |pos, &dir| {
+            *pos = pos.in_direction(dir);
+            Some(*pos)
+        })
+        .collect()
+}
+
+/// Picks a move for when there is no path worth following: the one that leaves the snake head the
+/// most empty cells to reach, preferring to keep going in `dir` when that is just as good. Returns
+/// `None` if every move is fatal.
+pub fn fallback_move(arena: Arena, snake: &[Pos], dir: Dir) -> Option<Dir> {
+    Dir::ALL
+        .into_iter()
+        .filter(|&next_dir| is_safe(arena, snake, next_dir))
+        .max_by_key(|&next_dir| {
+            let area = reachable_area(arena, &follow_path(snake, &[next_dir], snake[0]));
+            (area, next_dir == dir)
+        })
+}
+
+/// Counts the empty cells the snake head could reach if the rest of the snake stood still.
+fn reachable_area(arena: Arena, snake: &[Pos]) -> usize {
+    let mut board = Board::with_snake(arena, snake);
+    let mut stack = vec![snake[0]];
+    let mut area = 0;
+    while let Some(pos) = stack.pop() {
+        for dir in Dir::ALL {
+            let next_pos = pos.in_direction(dir);
+            if !next_pos.in_bounds(arena) {
+                continue;
+            }
+            // Visited nodes are marked as snake, so that they aren't counted twice
+            let next_node = board.get_mut(next_pos);
+            if next_node.kind == NodeKind::Snake {
+                continue;
+            }
+            next_node.kind = NodeKind::Snake;
+            area += 1;
+            stack.push(next_pos);
+        }
+    }
+    area
+}
+
+/// Whether moving the snake head in `dir` would not immediately kill it.
+pub fn is_safe(arena: Arena, snake: &[Pos], dir: Dir) -> bool {
+    let next_pos = snake[0].in_direction(dir);
+    // The tail moves out of the way as the head moves
+    next_pos.in_bounds(arena) && !snake[..snake.len() - 1].contains(&next_pos)
+}
+
+/// Moves the snake along `path`, growing it if it passes over `food`.
+fn follow_path(snake: &[Pos], path: &[Dir], food: Pos) -> Vec<Pos> {
+    let mut snake: VecDeque<Pos> = snake.iter().copied().collect();
+    let mut food = Some(food);
+    for &dir in path {
+        let head = snake[0].in_direction(dir);
+        snake.push_front(head);
+        if food == Some(head) {
+            food = None;
+        } else {
+            snake.pop_back();
+        }
+    }
+    snake.into()
+}
+
+/// Whether the snake head can always get behind its tail and follow its own body from there, which
+/// keeps it alive for as long as it likes. The way there must not pass over `food`, which would
+/// hold the tail back, and must leave the tail `slack` moves to spare.
+fn can_escape(arena: Arena, snake: &[Pos], food: Option<Pos>, slack: usize) -> bool {
+    let body = &snake[1..];
+    let is_goal = |pos| body.contains(&pos);
+    moving_body_search(arena, snake, food, slack, is_goal).is_some()
+}
+
+/// Whether the snake can still escape as in [`can_escape`] after moving in `dir`. If the move eats
+/// the food, the next one may be placed in the way and hold the tail back, so the tail is left a
+/// move to spare.
+fn can_escape_after(arena: Arena, snake: &[Pos], dir: Dir, food: Pos) -> bool {
+    let moved = follow_path(snake, &[dir], food);
+    if moved[0] == food {
+        can_escape(arena, &moved, None, 1)
+    } else {
+        can_escape(arena, &moved, Some(food), 0)
+    }
+}
+
+/// A breadth-first search from the snake head that lets it into a cell of its body once the tail
+/// has moved off it, as it will have by the time the head gets there. Returns the path to the
+/// first cell that `is_goal` accepts, never passing over `avoid` on the way. The body is taken to
+/// move off each cell `slack` moves late.
+fn moving_body_search(
+    arena: Arena,
+    snake: &[Pos],
+    avoid: Option<Pos>,
+    slack: usize,
+    is_goal: impl Fn(Pos) -> bool,
+) -> Option<Vec<Dir>> {
+    // The move from which each cell is free: straight away for empty cells, and once the tail has
+    // gone past for the body, a move later for each segment further from the tail
+    let mut free_from = vec![0; arena.area()];
+    for (i, &pos) in snake.iter().enumerate() {
+        free_from[arena.index(pos)] = snake.len() - i + slack;
+    }
+    // The direction each cell was reached from, and the move it was reached on
+    let mut reached: Vec<Option<(Dir, usize)>> = vec![None; arena.area()];
+    let mut queue = VecDeque::from([(snake[0], 0)]);
+    while let Some((pos, moves)) = queue.pop_front() {
+        for dir in Dir::ALL {
+            let next_pos = pos.in_direction(dir);
+            if !next_pos.in_bounds(arena) || next_pos == snake[0] || Some(next_pos) == avoid {
+                continue;
+            }
+            let index = arena.index(next_pos);
+            if reached[index].is_some() || free_from[index] > moves + 1 {
+                continue;
+            }
+            reached[index] = Some((dir, moves + 1));
+            if is_goal(next_pos) {
+                let mut path = Vec::new();
+                let mut pos = next_pos;
+                while let Some((dir, _)) = reached[arena.index(pos)] {
+                    path.push(dir);
+                    pos = pos.in_direction(dir.opposite());
+                }
+                path.reverse();
+                return Some(path);
+            }
+            queue.push_back((next_pos, moves + 1));
+        }
+    }
+    None
+}
+
+/// The number of orthogonal moves between `from` and `to`.
+fn distance(from: Pos, to: Pos) -> u32 {
+    (from.x - to.x).unsigned_abs() + (from.y - to.y).unsigned_abs()
 }
 
 impl Board {
-    fn new(snake: Vec<Pos>, food: Pos) -> Self {
+    fn new(arena: Arena, snake: Vec<Pos>, food: Pos) -> Self {
+        let mut board = Self::with_snake(arena, &snake);
+        board.get_mut(food).kind = NodeKind::Food;
+        board
+    }
+
+    fn with_snake(arena: Arena, snake: &[Pos]) -> Self {
         let mut board = Self {
-            nodes: [Node::default(); BOARD_SIZE],
+            arena,
+            nodes: vec![Node::default(); arena.area()],
         };
-        for segment in snake {
+        for &segment in snake {
             board.get_mut(segment).kind = NodeKind::Snake;
         }
-        board.get_mut(food).kind = NodeKind::Food;
         board
     }
 
+    /// Traces the path back from `pos` to the snake head and records the directions needed to get
+    /// there, in reverse, so that the final result is a path from the snake head to `pos`.
+    fn trace_path(&self, mut pos: Pos) -> Vec<Dir> {
+        let mut path = Vec::with_capacity((self.arena.width + self.arena.height) as usize);
+        while let Some(dir) = self.get(pos).dir_back {
+            path.push(dir.opposite());
+            pos = pos.in_direction(dir);
+        }
+        path.reverse();
+        path
+    }
+
     fn get(&self, pos: Pos) -> &Node {
-        &self.nodes[pos.y as usize * ARENA_WIDTH as usize + pos.x as usize]
+        &self.nodes[self.arena.index(pos)]
     }
 
     fn get_mut(&mut self, pos: Pos) -> &mut Node {
-        &mut self.nodes[pos.y as usize * ARENA_WIDTH as usize + pos.x as usize]
+        &mut self.nodes[self.arena.index(pos)]
+    }
+}
+
+impl HamiltonianCycle {
+    /// Empty cells kept between the snake head and its tail when taking shortcuts, as a safety
+    /// margin.
+    const SHORTCUT_BUFFER: usize = 3;
+
+    /// Creates a cycle through every cell of `arena`. Returns `None` if there is no such cycle,
+    /// which is the case when both dimensions are odd.
+    pub fn new(arena: Arena) -> Option<Self> {
+        let Arena { width, height } = arena;
+        if width < 2 || height < 2 || (width % 2 == 1 && height % 2 == 1) {
+            return None;
+        }
+        if height % 2 == 1 {
+            // Build the cycle on the transposed grid, which has an even number of rows
+            let transposed = Self::new(Arena::new(height, width))?;
+            let cells = transposed.cells.iter().map(|pos| Pos::new(pos.y, pos.x));
+            return Some(Self::from_cells(arena, cells.collect()));
+        }
+        let (width, height) = (width as i32, height as i32);
+        // Go right along the bottom row, then zig-zag up through every column but the first, and
+        // finally come back down the first column.
+        let mut cells: Vec<Pos> = (0..width).map(|x| Pos::new(x, 0)).collect();
+        for y in 1..height {
+            if y % 2 == 1 {
+                cells.extend((1..width).rev().map(|x| Pos::new(x, y)));
+            } else {
+                cells.extend((1..width).map(|x| Pos::new(x, y)));
+            }
+        }
+        cells.extend((1..height).rev().map(|y| Pos::new(0, y)));
+        Some(Self::from_cells(arena, cells))
+    }
+
+    fn from_cells(arena: Arena, cells: Vec<Pos>) -> Self {
+        let mut order = vec![0; cells.len()];
+        for (i, &pos) in cells.iter().enumerate() {
+            order[arena.index(pos)] = i;
+        }
+        Self {
+            arena,
+            cells,
+            order,
+        }
+    }
+
+    /// The same cycle, travelled in the opposite direction.
+    pub fn reversed(&self) -> Self {
+        let cells = self.cells.iter().rev().copied().collect();
+        Self::from_cells(self.arena, cells)
+    }
+
+    /// The cell after `pos` on the cycle.
+    pub fn next(&self, pos: Pos) -> Pos {
+        self.cells[(self.index(pos) + 1) % self.cells.len()]
+    }
+
+    /// How many steps along the cycle it takes to get from `from` to `to`.
+    pub fn distance(&self, from: Pos, to: Pos) -> usize {
+        let len = self.cells.len();
+        (self.index(to) + len - self.index(from)) % len
+    }
+
+    /// Whether the snake's body follows the cycle in order from its tail to its head, cutting
+    /// across only ever forwards, as it does while travelling this way round.
+    pub fn lies_along(&self, snake: &[Pos]) -> bool {
+        let steps: usize = snake
+            .windows(2)
+            .map(|pair| self.distance(pair[1], pair[0]))
+            .sum();
+        steps == self.distance(snake[snake.len() - 1], snake[0])
+    }
+
+    /// Chooses the snake's next move. The snake follows the cycle, but while it is short it cuts
+    /// across to a cell further along the cycle when that brings it closer to `food`. A shortcut is
+    /// never taken past the tail, so the body always stays behind the head in cycle order and the
+    /// cycle remains safe to follow.
+    pub fn next_move(&self, snake: &[Pos], food: Option<Pos>) -> Option<Dir> {
+        let head = snake[0];
+        let tail = snake[snake.len() - 1];
+        let mut max_skip = 1;
+        if snake.len() < self.cells.len() / 2 {
+            // Leave room for the segment the snake grows when it eats
+            let room = self
+                .distance(head, tail)
+                .saturating_sub(1 + Self::SHORTCUT_BUFFER);
+            let to_food = food.map_or(room, |food| self.distance(head, food));
+            max_skip = max_skip.max(room.min(to_food));
+        }
+        Dir::ALL
+            .into_iter()
+            .filter(|&dir| is_safe(self.arena, snake, dir))
+            .map(|dir| (dir, self.distance(head, head.in_direction(dir))))
+            .filter(|&(_, skip)| skip >= 1 && skip <= max_skip)
+            .max_by_key(|&(_, skip)| skip)
+            .map(|(dir, _)| dir)
+    }
+
+    fn index(&self, pos: Pos) -> usize {
+        self.order[self.arena.index(pos)]
+    }
+}
+
+impl SearchTrace {
+    fn new(arena: Arena) -> Self {
+        Self {
+            arena,
+            cells: vec![CellTrace::default(); arena.area()],
+        }
+    }
+
+    pub fn arena(&self) -> Arena {
+        self.arena
+    }
+
+    pub fn get(&self, pos: Pos) -> CellTrace {
+        self.cells[self.arena.index(pos)]
+    }
+
+    fn get_mut(&mut self, pos: Pos) -> &mut CellTrace {
+        &mut self.cells[self.arena.index(pos)]
     }
 }
 
@@ -152,12 +602,6 @@ impl PartialEq for HeapElem {
     }
 }
 
-impl Default for NodeKind {
-    fn default() -> Self {
-        NodeKind::Empty
-    }
-}
-
 impl Default for Cost {
     fn default() -> Self {
         Self {
@@ -172,12 +616,14 @@ impl Default for Cost {
 mod test {
     use super::*;
 
+    const ARENA: Arena = Arena::new(20, 20);
+
     #[test]
     fn straight_path_right() {
         let head = Pos::new(3, 3);
         let food = Pos::new(6, 3);
-        let path = find_path(vec![head], food);
-        let expected = vec![Dir::Right, Dir::Right, Dir::Right];
+        let path = find_path(ARENA, vec![head], food);
+        let expected = PathResult::Found(vec![Dir::Right, Dir::Right, Dir::Right]);
         assert_eq!(path, expected);
     }
 
@@ -185,8 +631,8 @@ mod test {
     fn straight_path_left() {
         let head = Pos::new(3, 0);
         let food = Pos::new(0, 0);
-        let path = find_path(vec![head], food);
-        let expected = vec![Dir::Left, Dir::Left, Dir::Left];
+        let path = find_path(ARENA, vec![head], food);
+        let expected = PathResult::Found(vec![Dir::Left, Dir::Left, Dir::Left]);
         assert_eq!(path, expected);
     }
 
@@ -194,8 +640,8 @@ mod test {
     fn straight_path_up() {
         let head = Pos::new(0, 0);
         let food = Pos::new(0, 3);
-        let path = find_path(vec![head], food);
-        let expected = vec![Dir::Up, Dir::Up, Dir::Up];
+        let path = find_path(ARENA, vec![head], food);
+        let expected = PathResult::Found(vec![Dir::Up, Dir::Up, Dir::Up]);
         assert_eq!(path, expected);
     }
 
@@ -203,8 +649,8 @@ mod test {
     fn straight_path_down() {
         let head = Pos::new(0, 3);
         let food = Pos::new(0, 0);
-        let path = find_path(vec![head], food);
-        let expected = vec![Dir::Down, Dir::Down, Dir::Down];
+        let path = find_path(ARENA, vec![head], food);
+        let expected = PathResult::Found(vec![Dir::Down, Dir::Down, Dir::Down]);
         assert_eq!(path, expected);
     }
 
@@ -212,11 +658,183 @@ mod test {
     fn path_across_board() {
         let head = Pos::new(0, 0);
         let food = Pos::new(19, 19);
-        let path = find_path(vec![head], food);
+        let path = match find_path(ARENA, vec![head], food) {
+            PathResult::Found(path) => path,
+            result => panic!("expected a path, got {:?}", result),
+        };
         // Count the number of Dir::Right's
         let right_count = path.iter().filter(|&&d| d == Dir::Right).count();
         let up_count = path.iter().filter(|&&d| d == Dir::Up).count();
         assert_eq!(right_count, 19);
         assert_eq!(up_count, 19);
     }
+
+    #[test]
+    fn path_starts_at_head() {
+        let head = Pos::new(3, 3);
+        let food = Pos::new(4, 5);
+        // A wall of snake directly above the head forces a detour to the right first
+        let snake = vec![head, Pos::new(3, 4), Pos::new(3, 5), Pos::new(2, 5)];
+        let path = find_path(ARENA, snake, food);
+        assert_eq!(path, PathResult::Found(vec![Dir::Right, Dir::Up, Dir::Up]));
+    }
+
+    #[test]
+    fn trace_records_search() {
+        let head = Pos::new(3, 3);
+        let food = Pos::new(6, 3);
+        let snake = vec![head, Pos::new(2, 3), Pos::new(1, 3)];
+        let (path, trace) = find_path_traced(ARENA, snake.clone(), food);
+        assert_eq!(path, find_path(ARENA, snake, food));
+        let head_trace = trace.get(head);
+        assert_eq!(head_trace.expansions, 1);
+        assert_eq!(head_trace.cost.map(|cost| cost.h_cost), Some(3));
+        let food_cost = trace.get(food).cost.unwrap();
+        assert_eq!((food_cost.g_cost, food_cost.h_cost), (3, 0));
+        // The search heads straight for the food, never looking behind the snake
+        assert_eq!(trace.get(Pos::new(0, 3)), CellTrace::default());
+    }
+
+    #[test]
+    fn safe_move_avoids_dead_end() {
+        // The food is at the end of a one cell wide corridor between the snake and the wall, so
+        // after eating it the snake would be stuck with its tail out of reach.
+        let mut snake: Vec<Pos> = (0..=6).rev().map(|x| Pos::new(x, 1)).collect();
+        snake.extend((0..=10).map(|x| Pos::new(x, 2)));
+        let food = Pos::new(0, 0);
+        match find_path(ARENA, snake.clone(), food) {
+            PathResult::Found(path) => assert_eq!(path[0], Dir::Down),
+            result => panic!("expected a path, got {:?}", result),
+        }
+        assert_eq!(
+            find_safe_move(ARENA, snake, food, Dir::Right),
+            Some(Dir::Right)
+        );
+    }
+
+    #[test]
+    fn safe_move_chases_tail_when_food_unreachable() {
+        let snake = vec![
+            Pos::new(3, 0),
+            Pos::new(2, 0),
+            Pos::new(1, 0),
+            Pos::new(1, 1),
+            Pos::new(0, 1),
+        ];
+        let food = Pos::new(0, 0);
+        assert_eq!(
+            find_path(ARENA, snake.clone(), food),
+            PathResult::Unreachable
+        );
+        assert_eq!(
+            find_safe_move(ARENA, snake, food, Dir::Right),
+            Some(Dir::Up)
+        );
+    }
+
+    #[test]
+    fn safe_path_traces_food_search() {
+        // The same dead end as above, where the path to the food is found but not taken
+        let mut snake: Vec<Pos> = (0..=6).rev().map(|x| Pos::new(x, 1)).collect();
+        snake.extend((0..=10).map(|x| Pos::new(x, 2)));
+        let food = Pos::new(0, 0);
+        let (path, trace) = find_safe_path_traced(ARENA, snake.clone(), food, Dir::Right);
+        assert_eq!(path, find_safe_path(ARENA, snake.clone(), food, Dir::Right));
+        let (_, food_trace) = find_path_traced(ARENA, snake, food);
+        assert_eq!(trace, food_trace);
+    }
+
+    #[test]
+    fn reachable_area_of_corner() {
+        // The snake walls off the bottom left 2x2 corner, with its head inside it
+        let snake = vec![
+            Pos::new(0, 0),
+            Pos::new(0, 1),
+            Pos::new(0, 2),
+            Pos::new(1, 2),
+            Pos::new(2, 2),
+            Pos::new(2, 1),
+            Pos::new(2, 0),
+        ];
+        assert_eq!(reachable_area(ARENA, &snake), 2);
+    }
+
+    #[test]
+    fn no_safe_move_when_boxed_in() {
+        // The head is in the corner, with its body on both sides
+        let snake = vec![
+            Pos::new(0, 0),
+            Pos::new(1, 0),
+            Pos::new(1, 1),
+            Pos::new(0, 1),
+            Pos::new(0, 2),
+        ];
+        assert_eq!(
+            find_path(ARENA, snake.clone(), Pos::new(5, 5)),
+            PathResult::NoSafeMove
+        );
+        assert_eq!(fallback_move(ARENA, &snake, Dir::Left), None);
+    }
+
+    #[test]
+    fn fallback_move_picks_largest_area() {
+        // The snake walls off the bottom left corner. Going left leads into it, going right leads
+        // to the rest of the board.
+        let snake = vec![
+            Pos::new(2, 0),
+            Pos::new(2, 1),
+            Pos::new(2, 2),
+            Pos::new(1, 2),
+            Pos::new(0, 2),
+            Pos::new(0, 3),
+            Pos::new(0, 4),
+        ];
+        assert_eq!(fallback_move(ARENA, &snake, Dir::Left), Some(Dir::Right));
+    }
+
+    #[test]
+    fn bfs_path_around_snake() {
+        let head = Pos::new(3, 3);
+        let food = Pos::new(4, 5);
+        let snake = vec![head, Pos::new(3, 4), Pos::new(3, 5), Pos::new(2, 5)];
+        let path = find_path_bfs(ARENA, snake, food);
+        assert_eq!(path, PathResult::Found(vec![Dir::Right, Dir::Up, Dir::Up]));
+    }
+
+    #[test]
+    fn hamiltonian_cycle_visits_every_cell() {
+        for (width, height) in [(20, 20), (4, 3), (3, 6), (2, 2)] {
+            let cycle = HamiltonianCycle::new(Arena::new(width, height)).unwrap();
+            let start = Pos::new(0, 0);
+            let mut pos = start;
+            let mut visited = vec![pos];
+            loop {
+                let next = cycle.next(pos);
+                // Each step on the cycle is a single move
+                assert_eq!((next.x - pos.x).abs() + (next.y - pos.y).abs(), 1);
+                if next == start {
+                    break;
+                }
+                visited.push(next);
+                pos = next;
+            }
+            visited.sort();
+            visited.dedup();
+            assert_eq!(visited.len(), (width * height) as usize);
+        }
+        assert!(HamiltonianCycle::new(Arena::new(5, 5)).is_none());
+    }
+
+    #[test]
+    fn hamiltonian_shortcut_stops_at_food() {
+        let cycle = HamiltonianCycle::new(ARENA).unwrap();
+        // The bottom row runs left to right, and row 1 right to left, so moving up from (2, 0)
+        // skips most of both rows.
+        let snake = vec![Pos::new(2, 0), Pos::new(1, 0), Pos::new(0, 0)];
+        assert_eq!(cycle.next_move(&snake, Some(Pos::new(2, 1))), Some(Dir::Up));
+        assert_eq!(
+            cycle.next_move(&snake, Some(Pos::new(5, 0))),
+            Some(Dir::Right)
+        );
+    }
 }
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use super::snake::SnakeMovement;
+
+/// Pauses the game with `P`, and while it is paused, advances it by a single move with `N`.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SingleStep>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(pause_input.before(SnakeMovement)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused)
+                    .with_system(paused_input.before(SnakeMovement)),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(announce_pause));
+    }
+}
+
+/// Set when the game should advance by one move despite being paused.
+#[derive(Default)]
+pub struct SingleStep(bool);
+
+impl SingleStep {
+    /// Returns whether a single step was requested, and clears the request.
+    pub fn take(&mut self) -> bool {
+        std::mem::take(&mut self.0)
+    }
+}
+
+fn pause_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::P) {
+        game_state.push(GameState::Paused).unwrap();
+        // The new state's systems run in the same frame, and would otherwise see the press too
+        keyboard_input.reset(KeyCode::P);
+    }
+}
+
+fn paused_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut game_state: ResMut<State<GameState>>,
+    mut single_step: ResMut<SingleStep>,
+) {
+    if keyboard_input.just_pressed(KeyCode::P) {
+        game_state.pop().unwrap();
+        keyboard_input.reset(KeyCode::P);
+        println!("Resumed");
+    } else if keyboard_input.just_pressed(KeyCode::N) {
+        single_step.0 = true;
+    }
+}
+
+fn announce_pause() {
+    println!("Paused");
+}
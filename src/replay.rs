@@ -0,0 +1,350 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::components::*;
+use super::config::Config;
+use super::controller::{Controller, SnakeController};
+use super::food::FoodRespawn;
+use super::game::GameStart;
+use super::simulation::Simulation;
+use super::snake::{SnakeMoveTick, SnakeMovement};
+
+/// The directory every game is recorded to.
+pub const REPLAY_DIR: &str = "replays";
+
+/// Records every game to a [`Replay`] file in [`REPLAY_DIR`] once it is over.
+pub struct RecordPlugin;
+
+impl Plugin for RecordPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Playing).with_system(record_start.after(GameStart)),
+        )
+        .add_system(
+            record_move
+                .after(SnakeMovement)
+                .after(FoodRespawn)
+                .with_run_criteria(SnakeMoveTick),
+        );
+        for ended in GameState::ENDED {
+            app.add_system_set(SystemSet::on_enter(ended).with_system(record_save));
+        }
+    }
+}
+
+/// Keeps track of whether the game still follows the [`Playback`], so that the replay's food is
+/// only placed for as long as it does.
+pub struct PlaybackPlugin;
+
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            playback_follow
+                .after(SnakeMovement)
+                .before(FoodRespawn)
+                .with_run_criteria(SnakeMoveTick),
+        );
+    }
+}
+
+/// Everything needed to play a game again, move for move.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    /// The name of the controller that played the game.
+    pub ai: String,
+    pub seed: u64,
+    pub config: Config,
+    /// The snake at the start of the game, head first.
+    pub snake: Vec<Pos>,
+    /// Every food placed, in order. The first is placed before the first move, and each one after
+    /// as the one before it is eaten.
+    pub foods: Vec<Pos>,
+    /// The direction of every move, including the fatal one if the snake died.
+    pub moves: Vec<Dir>,
+}
+
+/// The game being recorded.
+pub struct Recording(pub Replay);
+
+/// The replay being played back, when there is one. A [`ReplayController`] makes its moves, and
+/// its food is placed instead of random food until the snake is taken over and leaves its route.
+pub struct Playback {
+    pub replay: Replay,
+    /// The first of the game's moves that differed from the replay's, if any.
+    left_at: Option<usize>,
+}
+
+/// Makes the moves of a replay, in place of an AI.
+pub struct ReplayController {
+    moves: Vec<Dir>,
+}
+
+impl Replay {
+    /// Starts recording `sim`, which must not have made any moves yet.
+    pub fn new(ai: &str, seed: u64, config: &Config, sim: &Simulation) -> Self {
+        Self {
+            ai: ai.to_owned(),
+            seed,
+            config: config.clone(),
+            snake: sim.snake().iter().copied().collect(),
+            foods: sim.food().into_iter().collect(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// Reads a replay from `path`, and checks that it can be played back.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read replay '{}': {}", path, err))?;
+        let replay: Self = serde_json::from_str(&text)
+            .map_err(|err| format!("Invalid replay file '{}': {}", path, err))?;
+        replay
+            .config
+            .validate()
+            .map_err(|err| format!("Invalid replay file '{}': {}", path, err))?;
+        // The snake is set up from the config, so it had better agree
+        if !replay.config.new_game().snake().iter().eq(&replay.snake) {
+            return Err(format!(
+                "Invalid replay file '{}': the snake doesn't start where its config puts it",
+                path
+            ));
+        }
+        if replay.foods.is_empty() {
+            return Err(format!("Invalid replay file '{}': there is no food", path));
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let save = || {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(path, serde_json::to_string(self).unwrap())
+        };
+        save().map_err(|err| format!("Couldn't save replay to '{}': {}", path.display(), err))
+    }
+
+    /// The file the replay is saved to in [`REPLAY_DIR`]. A later game with the same AI, arena and
+    /// seed replaces it.
+    pub fn file_name(&self) -> String {
+        let arena = self.config.arena;
+        format!(
+            "{}-{}x{}-seed{}.json",
+            self.ai, arena.width, arena.height, self.seed
+        )
+    }
+
+    /// The food placed once the snake has eaten `eaten` foods, if there was one.
+    pub fn food(&self, eaten: usize) -> Option<Pos> {
+        self.foods.get(eaten).copied()
+    }
+
+    /// Sets up the game as it was at the start, with its first food.
+    pub fn new_game(&self) -> Simulation {
+        let mut sim = self.config.new_game();
+        sim.place_food(self.foods[0]);
+        sim
+    }
+
+    /// Forgets the moves made after `sim`, and the food placed after them, once the game has been
+    /// rewound to it.
+    pub fn rewind_to(&mut self, sim: &Simulation) {
+        self.moves.truncate(sim.moves());
+        self.foods.truncate(sim.eaten() + 1);
+    }
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            left_at: None,
+        }
+    }
+
+    /// The replay's next food for `sim`, as long as every move so far was the replay's.
+    pub fn food(&self, sim: &Simulation) -> Option<Pos> {
+        if self.left_at.is_some_and(|at| at < sim.moves()) {
+            return None;
+        }
+        self.replay.food(sim.eaten())
+    }
+
+    /// Notes that the last move `sim` tried, fatal or not, went in `dir`.
+    fn follow(&mut self, sim: &Simulation, dir: Dir) {
+        let index = sim.moves_tried() - 1;
+        // Any later move that differed has been undone if the game was rewound or restarted
+        if self.left_at.is_none_or(|at| at >= index) {
+            self.left_at = (self.replay.moves.get(index) != Some(&dir)).then_some(index);
+        }
+    }
+}
+
+impl ReplayController {
+    pub fn new(replay: &Replay) -> Self {
+        Self {
+            moves: replay.moves.clone(),
+        }
+    }
+}
+
+impl SnakeController for ReplayController {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    /// Goes by the number of moves made, so that it follows the game back when it is rewound.
+    fn next_dir(&mut self, sim: &Simulation) -> Dir {
+        self.moves.get(sim.moves()).copied().unwrap_or(sim.dir())
+    }
+}
+
+fn record_start(
+    mut commands: Commands,
+    config: Res<Config>,
+    seed: Res<GameSeed>,
+    sim: Res<Simulation>,
+    controller: Res<Controller>,
+) {
+    let replay = Replay::new(controller.0.name(), seed.0, &config, &sim);
+    commands.insert_resource(Recording(replay));
+}
+
+fn record_move(
+    sim: Res<Simulation>,
+    head_query: Query<&SnakeHead>,
+    mut recording: ResMut<Recording>,
+) {
+    let replay = &mut recording.0;
+    replay.moves.push(head_query.single().next_dir);
+    // A new food is placed as soon as the last one is eaten
+    if sim.food().is_some() && sim.food() != replay.foods.last().copied() {
+        replay.foods.extend(sim.food());
+    }
+}
+
+fn playback_follow(
+    sim: Res<Simulation>,
+    head_query: Query<&SnakeHead>,
+    mut playback: ResMut<Playback>,
+) {
+    playback.follow(&sim, head_query.single().next_dir);
+}
+
+fn record_save(recording: Res<Recording>, headless: Option<Res<Headless>>) {
+    let path = Path::new(REPLAY_DIR).join(recording.0.file_name());
+    match recording.0.save(&path) {
+        Ok(()) if headless.is_none() => println!("Replay saved to {}", path.display()),
+        Ok(()) => {}
+        Err(err) => eprintln!("{}", err),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::controller::controller_by_name;
+    use crate::simulation::StepOutcome;
+
+    /// Records a game of the A* AI in an 8x8 arena, until `until` is true of it.
+    fn record_game(until: impl Fn(&Simulation) -> bool) -> (Replay, Simulation) {
+        let config = Config {
+            arena: Arena::new(8, 8),
+            ..Default::default()
+        };
+        let mut rng = GameRng::new(3);
+        let mut controller = controller_by_name("astar").unwrap();
+        let mut sim = config.new_game();
+        sim.spawn_food(&mut rng.0);
+        let mut replay = Replay::new("astar", 3, &config, &sim);
+        while !until(&sim) {
+            let dir = controller.next_dir(&sim);
+            replay.moves.push(dir);
+            if let StepOutcome::Ate = sim.step(dir) {
+                replay.foods.extend(sim.spawn_food(&mut rng.0));
+            }
+        }
+        (replay, sim)
+    }
+
+    #[test]
+    fn replay_plays_game_again() {
+        let (replay, sim) = record_game(Simulation::is_over);
+
+        let mut replay_controller = ReplayController::new(&replay);
+        let mut played = replay.new_game();
+        while !played.is_over() {
+            if let StepOutcome::Ate = played.step(replay_controller.next_dir(&played)) {
+                played.place_food(replay.food(played.eaten()).unwrap());
+            }
+        }
+        assert_eq!(played.snake(), sim.snake());
+        assert_eq!(played.moves(), sim.moves());
+    }
+
+    #[test]
+    fn playback_food_stops_once_taken_over() {
+        let (replay, _) = record_game(|sim| sim.eaten() == 3);
+
+        let mut playback = Playback::new(replay.clone());
+        let mut played = replay.new_game();
+        while played.eaten() == 0 {
+            let dir = replay.moves[played.moves()];
+            played.step(dir);
+            playback.follow(&played, dir);
+        }
+        assert_eq!(playback.food(&played), replay.food(1));
+
+        // Turn off the replay's route. Only the number of moves made matters to the playback.
+        let index = played.moves();
+        let turn = Dir::ALL
+            .into_iter()
+            .find(|&dir| dir != replay.moves[index])
+            .unwrap();
+        played.step(replay.moves[index]);
+        playback.follow(&played, turn);
+        assert_eq!(playback.food(&played), None);
+        // Rewinding and making the replay's move instead gets back on its route
+        playback.follow(&played, replay.moves[index]);
+        assert_eq!(playback.food(&played), replay.food(1));
+    }
+
+    #[test]
+    fn playback_follows_fatal_first_move() {
+        // The snake starts against the right wall, heading into it
+        let config = Config {
+            arena: Arena::new(8, 8),
+            start_pos: Some(Pos::new(7, 5)),
+            ..Default::default()
+        };
+        let mut sim = config.new_game();
+        sim.spawn_food(&mut GameRng::new(3).0);
+        let mut replay = Replay::new("human", 3, &config, &sim);
+        replay.moves.push(sim.dir());
+        assert_eq!(sim.step(sim.dir()), StepOutcome::DiedByWall);
+
+        let mut playback = Playback::new(replay.clone());
+        let mut played = replay.new_game();
+        let mut replay_controller = ReplayController::new(&replay);
+        let dir = replay_controller.next_dir(&played);
+        assert_eq!(played.step(dir), StepOutcome::DiedByWall);
+        playback.follow(&played, dir);
+        assert_eq!(playback.left_at, None);
+        playback.follow(&played, dir.opposite());
+        assert_eq!(playback.left_at, Some(0));
+    }
+
+    #[test]
+    fn rewinding_forgets_later_moves() {
+        let (mut replay, _) = record_game(Simulation::is_over);
+        // The same game, cut short just after the second food was eaten
+        let (_, rewound) = record_game(|sim| sim.eaten() == 2);
+        replay.rewind_to(&rewound);
+        assert_eq!(replay.moves.len(), rewound.moves());
+        assert_eq!(replay.foods.len(), 3);
+        assert_eq!(replay.foods.last().copied(), rewound.food());
+    }
+}
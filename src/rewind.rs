@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use super::components::*;
+use super::config::Config;
+use super::consts::*;
+use super::controller::{controller_by_name, Controller, CONTROLLER_NAMES};
+use super::game::{respawn_game, GameEntity, GameStart};
+use super::heatmap::{no_heatmap, Heatmap};
+use super::pause::SingleStep;
+use super::replay::Recording;
+use super::simulation::Simulation;
+use super::snake::{SnakeMoveTick, SnakeMovement};
+
+/// Keeps the last [`REWIND_MOVES`] moves of the game, so that it can be stepped back through with
+/// `B` while paused or once it is over. `C` switches to the next controller, to carry on with
+/// another AI or by hand from where the game was rewound to.
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_event::<Rewound>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(history_clear.after(GameStart)),
+            )
+            .add_system(
+                history_record
+                    .before(SnakeMovement)
+                    .with_run_criteria(SnakeMoveTick),
+            )
+            // Rewinding comes before the frame's update, so that the respawned entities exist by the
+            // time the snake moves, and so that it can still call off a step asked for with `N`
+            .add_system_set_to_stage(
+                CoreStage::PreUpdate,
+                SystemSet::new()
+                    .with_system(rewind_input.after(InputSystem))
+                    .with_system(rewind_respawn.after(rewind_input)),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(controller_switch));
+        for ended in GameState::ENDED {
+            app.add_system_set(SystemSet::on_update(ended).with_system(controller_switch));
+        }
+    }
+}
+
+/// The game as it was before a move.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub sim: Simulation,
+    /// The random numbers still to come, so that the food lands where it did after rewinding.
+    pub rng: GameRng,
+}
+
+/// Sent when the game has been stepped back a move.
+pub struct Rewound;
+
+/// The snapshots taken before the most recent moves of the current game, oldest first.
+#[derive(Default)]
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    rewound: bool,
+}
+
+impl History {
+    /// Takes a snapshot before a move, forgetting the oldest one if there are already
+    /// [`REWIND_MOVES`].
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() == REWIND_MOVES {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// Steps back a move, returning the game as it was before it, if it is still remembered.
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        let snapshot = self.snapshots.pop_back()?;
+        self.rewound = true;
+        Some(snapshot)
+    }
+
+    /// Whether the current game has been rewound at all. Such games don't count for high scores.
+    pub fn rewound(&self) -> bool {
+        self.rewound
+    }
+}
+
+fn history_clear(mut history: ResMut<History>) {
+    *history = History::default();
+}
+
+fn history_record(sim: Res<Simulation>, rng: Res<GameRng>, mut history: ResMut<History>) {
+    history.push(Snapshot {
+        sim: sim.clone(),
+        rng: rng.clone(),
+    });
+}
+
+/// Steps back a move while paused, calling off any step still to be made. Once the game is over,
+/// it is paused again at the move before the end, to be carried on with.
+fn rewind_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut history: ResMut<History>,
+    mut sim: ResMut<Simulation>,
+    mut rng: ResMut<GameRng>,
+    mut game_state: ResMut<State<GameState>>,
+    mut single_step: ResMut<SingleStep>,
+    mut rewound_writer: EventWriter<Rewound>,
+) {
+    let rewindable = matches!(
+        game_state.current(),
+        GameState::Paused | GameState::GameOver | GameState::Won
+    );
+    if !rewindable || !keyboard_input.just_pressed(KeyCode::B) {
+        return;
+    }
+    let snapshot = match history.pop() {
+        Some(snapshot) => snapshot,
+        None => {
+            println!("Can't rewind any further");
+            return;
+        }
+    };
+    *sim = snapshot.sim;
+    *rng = snapshot.rng;
+    single_step.take();
+    if game_state.current().is_ended() {
+        game_state.set(GameState::Paused).unwrap();
+    }
+    rewound_writer.send(Rewound);
+    println!("Rewound to move {}", sim.moves());
+}
+
+/// Brings everything else back in line with the rewound game.
+fn rewind_respawn(
+    mut commands: Commands,
+    sim: Res<Simulation>,
+    mut body: ResMut<SnakeBody>,
+    mut controller: ResMut<Controller>,
+    recording: Option<ResMut<Recording>>,
+    mut rewound_reader: EventReader<Rewound>,
+    entity_query: Query<Entity, GameEntity>,
+) {
+    if rewound_reader.iter().count() == 0 {
+        return;
+    }
+    respawn_game(&mut commands, &sim, &mut body, &entity_query);
+    // Whatever the controller planned was for moves that are now undone
+    controller.0.reset();
+    if let Some(mut recording) = recording {
+        recording.0.rewind_to(&sim);
+    }
+}
+
+/// Switches to the next controller that can play in the arena, keeping the heatmap going.
+fn controller_switch(
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<Config>,
+    heatmap: Res<Heatmap>,
+    mut controller: ResMut<Controller>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::C) {
+        return;
+    }
+    // After the current controller, or from the first one if it isn't listed, like when replaying
+    let start = CONTROLLER_NAMES
+        .iter()
+        .position(|&name| name == controller.0.name())
+        .map_or(0, |index| index + 1);
+    let next = CONTROLLER_NAMES
+        .iter()
+        .cycle()
+        .skip(start)
+        .take(CONTROLLER_NAMES.len())
+        .filter_map(|name| controller_by_name(name))
+        .find(|next| next.supports(config.arena));
+    if let Some(mut next) = next {
+        next.trace_searches(*heatmap != Heatmap::Off);
+        controller.0 = next;
+        println!("AI: {}", controller.0.name());
+        if *heatmap != Heatmap::Off && !controller.0.can_trace() {
+            println!("{}", no_heatmap(&controller));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn history_keeps_recent_moves() {
+        let sim = Config::default().new_game();
+        let mut history = History::default();
+        // Each snapshot is told apart by the seed of its random numbers
+        let snapshots = REWIND_MOVES + 5;
+        for seed in 0..snapshots {
+            history.push(Snapshot {
+                sim: sim.clone(),
+                rng: GameRng::new(seed as u64),
+            });
+        }
+        assert!(!history.rewound());
+        let first_numbers: Vec<u64> = (0..snapshots)
+            .map(|seed| GameRng::new(seed as u64).gen())
+            .collect();
+        let mut seeds = Vec::new();
+        while let Some(mut snapshot) = history.pop() {
+            let first = snapshot.rng.gen::<u64>();
+            seeds.push(first_numbers.iter().position(|&number| number == first));
+        }
+        assert!(history.rewound());
+        let expected: Vec<_> = (5..snapshots).rev().map(Some).collect();
+        assert_eq!(seeds, expected);
+    }
+}
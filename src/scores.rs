@@ -0,0 +1,167 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::components::*;
+use super::controller::Controller;
+use super::rewind::History;
+use super::simulation::Simulation;
+use super::steering::Steering;
+
+/// The file the high scores are kept in.
+pub const SCORES_PATH: &str = "scores.json";
+
+/// Records every finished game in the [`HighScores`], and saves them whenever one is beaten. Games
+/// that were rewound, or that the player steered any of, don't count.
+pub struct ScoresPlugin;
+
+impl Plugin for ScoresPlugin {
+    fn build(&self, app: &mut App) {
+        for ended in GameState::ENDED {
+            app.add_system_set(SystemSet::on_enter(ended).with_system(scores_record));
+        }
+    }
+}
+
+/// The best game played by an AI on an arena size and seed.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub ai: String,
+    pub arena: Arena,
+    pub seed: u64,
+    pub len: usize,
+    pub moves: usize,
+}
+
+/// The high scores, loaded from and saved to a file. Also a resource.
+#[derive(Debug, Default)]
+pub struct HighScores {
+    /// The file the scores are saved to, or `None` if they are kept only until the game quits.
+    path: Option<String>,
+    scores: Vec<HighScore>,
+}
+
+impl HighScore {
+    /// Whether this game was better than `other`: the snake grew longer, or as long in fewer moves.
+    pub fn beats(&self, other: &HighScore) -> bool {
+        self.len > other.len || (self.len == other.len && self.moves < other.moves)
+    }
+
+    fn same_game(&self, other: &HighScore) -> bool {
+        self.ai == other.ai && self.arena == other.arena && self.seed == other.seed
+    }
+}
+
+impl HighScores {
+    /// Reads the high scores from `path`. There are none yet if the file doesn't exist.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let scores = if Path::new(path).exists() {
+            let text = std::fs::read_to_string(path)
+                .map_err(|err| format!("Couldn't read high scores '{}': {}", path, err))?;
+            serde_json::from_str(&text)
+                .map_err(|err| format!("Invalid high scores file '{}': {}", path, err))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path: Some(path.to_owned()),
+            scores,
+        })
+    }
+
+    /// Saves the high scores to their file, if they have one.
+    pub fn save(&self) -> Result<(), String> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let text = serde_json::to_string_pretty(&self.scores).unwrap();
+        std::fs::write(path, text)
+            .map_err(|err| format!("Couldn't save high scores to '{}': {}", path, err))
+    }
+
+    /// Records a finished game. Returns whether it is a new high score for its AI, arena size and
+    /// seed.
+    pub fn record(&mut self, score: HighScore) -> bool {
+        match self.scores.iter_mut().find(|best| best.same_game(&score)) {
+            Some(best) if score.beats(best) => *best = score,
+            Some(_) => return false,
+            None => self.scores.push(score),
+        }
+        true
+    }
+
+    /// The high scores of `ai` on `arena` over every seed, best first.
+    pub fn table(&self, ai: &str, arena: Arena) -> Vec<&HighScore> {
+        let mut table: Vec<&HighScore> = self
+            .scores
+            .iter()
+            .filter(|score| score.ai == ai && score.arena == arena)
+            .collect();
+        table.sort_by(|a, b| b.len.cmp(&a.len).then(a.moves.cmp(&b.moves)));
+        table
+    }
+}
+
+fn scores_record(
+    sim: Res<Simulation>,
+    controller: Res<Controller>,
+    seed: Res<GameSeed>,
+    mut scores: ResMut<HighScores>,
+    history: Option<Res<History>>,
+    steering: Option<Res<Steering>>,
+) {
+    if history.is_some_and(|history| history.rewound())
+        || steering.is_some_and(|steering| steering.player_steered())
+    {
+        return;
+    }
+    let score = HighScore {
+        ai: controller.0.name().to_owned(),
+        arena: sim.arena(),
+        seed: seed.0,
+        len: sim.len(),
+        moves: sim.moves(),
+    };
+    if scores.record(score) {
+        println!("New high score for seed {}!", seed.0);
+        if let Err(err) = scores.save() {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn score(seed: u64, len: usize, moves: usize) -> HighScore {
+        HighScore {
+            ai: "astar".to_owned(),
+            arena: Arena::default(),
+            seed,
+            len,
+            moves,
+        }
+    }
+
+    #[test]
+    fn only_better_games_are_recorded() {
+        let mut scores = HighScores::default();
+        assert!(scores.record(score(1, 30, 200)));
+        assert!(!scores.record(score(1, 20, 100)));
+        assert!(!scores.record(score(1, 30, 250)));
+        assert!(scores.record(score(1, 30, 150)));
+        assert!(scores.record(score(2, 10, 50)));
+        assert!(scores.record(HighScore {
+            ai: "bfs".to_owned(),
+            ..score(1, 10, 50)
+        }));
+        assert_eq!(
+            scores.table("astar", Arena::default()),
+            vec![&score(1, 30, 150), &score(2, 10, 50)]
+        );
+        assert!(scores.table("astar", Arena::new(10, 10)).is_empty());
+    }
+}
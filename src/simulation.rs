@@ -0,0 +1,294 @@
+use std::collections::VecDeque;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::components::{Arena, Dir, Pos};
+use super::config::Config;
+
+/// The rules of the game, independent of Bevy. The ECS systems step a `Simulation` and mirror its
+/// state onto their entities, so the same rules can be unit tested or run in a tight loop.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    arena: Arena,
+    /// Snake positions, head first.
+    snake: VecDeque<Pos>,
+    /// The direction of the last move.
+    dir: Dir,
+    food: Option<Pos>,
+    alive: bool,
+    /// The number of moves made so far.
+    moves: usize,
+    /// The number of foods eaten so far.
+    eaten: usize,
+    /// The number of moves made when the snake last ate, or 0 if it hasn't yet.
+    ate_at: usize,
+}
+
+/// The result of advancing the game by one move.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StepOutcome {
+    /// The snake moved into an empty cell.
+    Moved,
+    /// The snake moved onto the food and grew by one segment.
+    Ate,
+    /// The snake ran into the edge of the arena.
+    DiedByWall,
+    /// The snake ran into its own body.
+    DiedBySelf,
+    /// The snake ate the last food and now fills the whole arena.
+    Won,
+}
+
+impl Simulation {
+    /// Creates a snake of length `len` with its head at `head`, facing `dir`, with the rest of its
+    /// body trailing straight behind it. No food is placed.
+    pub fn new(arena: Arena, head: Pos, len: usize, dir: Dir) -> Self {
+        let mut snake = VecDeque::with_capacity(len);
+        let mut pos = head;
+        for _ in 0..len {
+            snake.push_back(pos);
+            pos = pos.in_direction(dir.opposite());
+        }
+        Self {
+            arena,
+            snake,
+            dir,
+            food: None,
+            alive: true,
+            moves: 0,
+            eaten: 0,
+            ate_at: 0,
+        }
+    }
+
+    pub fn arena(&self) -> Arena {
+        self.arena
+    }
+
+    pub fn snake(&self) -> &VecDeque<Pos> {
+        &self.snake
+    }
+
+    pub fn head(&self) -> Pos {
+        self.snake[0]
+    }
+
+    pub fn tail(&self) -> Pos {
+        self.snake[self.snake.len() - 1]
+    }
+
+    // The snake always has a head, so it is never empty
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.snake.len()
+    }
+
+    pub fn dir(&self) -> Dir {
+        self.dir
+    }
+
+    pub fn food(&self) -> Option<Pos> {
+        self.food
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// The number of moves the snake tried to make, which unlike [`Self::moves`] counts the one
+    /// that killed it.
+    pub fn moves_tried(&self) -> usize {
+        self.moves + usize::from(!self.alive)
+    }
+
+    pub fn eaten(&self) -> usize {
+        self.eaten
+    }
+
+    /// The number of moves made since the snake last ate, or since the start if it hasn't yet.
+    pub fn moves_since_food(&self) -> usize {
+        self.moves - self.ate_at
+    }
+
+    pub fn has_won(&self) -> bool {
+        self.len() == self.arena.area()
+    }
+
+    /// Whether the game has ended, either because the snake died or because it won.
+    pub fn is_over(&self) -> bool {
+        !self.alive || self.has_won()
+    }
+
+    pub fn place_food(&mut self, pos: Pos) {
+        self.food = Some(pos);
+    }
+
+    /// Places the food on a random cell of the arena that isn't covered by the snake, and returns
+    /// its position. Returns `None` if the snake covers every cell.
+    pub fn spawn_food(&mut self, rng: &mut impl Rng) -> Option<Pos> {
+        let free_cells: Vec<Pos> = self
+            .arena
+            .cells()
+            .filter(|pos| !self.snake.contains(pos))
+            .collect();
+        let pos = *free_cells.choose(rng)?;
+        self.place_food(pos);
+        Some(pos)
+    }
+
+    /// Moves the snake one cell in `dir`. If the snake dies, it is left where it was before the
+    /// move.
+    pub fn step(&mut self, dir: Dir) -> StepOutcome {
+        debug_assert!(!self.is_over(), "the game is over");
+        let head = self.head().in_direction(dir);
+        if !head.in_bounds(self.arena) {
+            self.alive = false;
+            return StepOutcome::DiedByWall;
+        }
+        let ate = self.food == Some(head);
+        // Unless the snake grows, the tail moves out of the way as the head moves in
+        let body_len = if ate { self.len() } else { self.len() - 1 };
+        if self.snake.iter().take(body_len).any(|&pos| pos == head) {
+            self.alive = false;
+            return StepOutcome::DiedBySelf;
+        }
+        self.dir = dir;
+        self.moves += 1;
+        self.snake.push_front(head);
+        if ate {
+            self.food = None;
+            self.eaten += 1;
+            self.ate_at = self.moves;
+            if self.has_won() {
+                StepOutcome::Won
+            } else {
+                StepOutcome::Ate
+            }
+        } else {
+            self.snake.pop_back();
+            StepOutcome::Moved
+        }
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Config::default().new_game()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn snake(sim: &Simulation) -> Vec<Pos> {
+        sim.snake().iter().copied().collect()
+    }
+
+    #[test]
+    fn new_snake_trails_behind_head() {
+        let sim = Simulation::new(Arena::default(), Pos::new(5, 5), 3, Dir::Up);
+        assert_eq!(
+            snake(&sim),
+            vec![Pos::new(5, 5), Pos::new(5, 4), Pos::new(5, 3)]
+        );
+    }
+
+    #[test]
+    fn step_moves_whole_snake() {
+        let mut sim = Simulation::new(Arena::default(), Pos::new(5, 5), 3, Dir::Right);
+        assert_eq!(sim.step(Dir::Up), StepOutcome::Moved);
+        assert_eq!(
+            snake(&sim),
+            vec![Pos::new(5, 6), Pos::new(5, 5), Pos::new(4, 5)]
+        );
+        assert_eq!(sim.dir(), Dir::Up);
+    }
+
+    #[test]
+    fn eating_grows_snake() {
+        let mut sim = Simulation::new(Arena::default(), Pos::new(5, 5), 3, Dir::Right);
+        sim.place_food(Pos::new(6, 5));
+        assert_eq!(sim.step(Dir::Right), StepOutcome::Ate);
+        assert_eq!(sim.len(), 4);
+        assert_eq!(sim.tail(), Pos::new(3, 5));
+        assert_eq!(sim.food(), None);
+        assert_eq!(sim.eaten(), 1);
+        assert_eq!(sim.moves_since_food(), 0);
+        sim.step(Dir::Up);
+        assert_eq!(sim.moves_since_food(), 1);
+    }
+
+    #[test]
+    fn filling_arena_wins() {
+        // A snake zig-zagging along every row but the top one, with its head in the top left
+        let mut sim = Simulation::new(Arena::default(), Pos::new(0, 0), 1, Dir::Right);
+        let Arena { width, height } = sim.arena();
+        let (width, height) = (width as i32, height as i32);
+        sim.snake = (0..height - 1)
+            .flat_map(|y| {
+                let row = (0..width).map(move |x| Pos::new(x, y));
+                // Every other row runs backwards, so that the body is connected
+                if y % 2 == 0 {
+                    row.rev().collect::<Vec<_>>()
+                } else {
+                    row.collect()
+                }
+            })
+            .rev()
+            .collect();
+        assert_eq!(sim.head(), Pos::new(0, height - 2));
+        // Eat along the top row
+        for x in 0..width {
+            assert!(!sim.is_over());
+            sim.place_food(Pos::new(x, height - 1));
+            let outcome = sim.step(if x == 0 { Dir::Up } else { Dir::Right });
+            if x == width - 1 {
+                assert_eq!(outcome, StepOutcome::Won);
+            } else {
+                assert_eq!(outcome, StepOutcome::Ate);
+            }
+        }
+        assert!(sim.is_over());
+    }
+
+    #[test]
+    fn food_spawns_on_free_cell() {
+        let mut rng = rand::thread_rng();
+        // A snake covering every cell but the top right corner
+        let mut sim = Simulation::new(Arena::default(), Pos::new(0, 0), 1, Dir::Right);
+        sim.snake = sim.arena().cells().collect();
+        let corner = sim.snake.pop_back().unwrap();
+        for _ in 0..10 {
+            assert_eq!(sim.spawn_food(&mut rng), Some(corner));
+        }
+        sim.snake.push_back(corner);
+        assert_eq!(sim.spawn_food(&mut rng), None);
+    }
+
+    #[test]
+    fn leaving_arena_kills_snake() {
+        let mut sim = Simulation::new(Arena::default(), Pos::new(0, 5), 3, Dir::Up);
+        assert_eq!(sim.step(Dir::Left), StepOutcome::DiedByWall);
+        assert!(sim.is_over());
+        assert_eq!(sim.head(), Pos::new(0, 5));
+    }
+
+    #[test]
+    fn turning_back_kills_snake() {
+        let mut sim = Simulation::new(Arena::default(), Pos::new(5, 5), 3, Dir::Right);
+        assert_eq!(sim.step(Dir::Left), StepOutcome::DiedBySelf);
+        assert!(sim.is_over());
+    }
+
+    #[test]
+    fn snake_can_follow_its_tail() {
+        // A 2x2 loop, where the head moves into the cell the tail is leaving
+        let mut sim = Simulation::new(Arena::default(), Pos::new(5, 5), 4, Dir::Right);
+        sim.step(Dir::Up);
+        sim.step(Dir::Left);
+        assert_eq!(sim.step(Dir::Down), StepOutcome::Moved);
+        assert_eq!(sim.head(), Pos::new(4, 5));
+    }
+}
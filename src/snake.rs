@@ -1,171 +1,179 @@
-use bevy::{prelude::*, time::FixedTimestep};
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
 
 use super::components::*;
-use super::consts::*;
-use super::pathfinding::find_path;
+use super::config::Config;
+use super::controller::Controller;
+use super::game::end_game;
+use super::pause::SingleStep;
+use super::simulation::{Simulation, StepOutcome};
+use super::speed::{move_timer, Speed};
+use super::steering::{Steering, SteeringMode};
 
 pub struct SnakePlugin;
 
+/// Label for the systems that move the snake, so that systems reacting to the snake's new position
+/// can run after it within the same frame.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SnakeMovement;
+
+/// Label for the run criterion that decides when the snake moves. Systems reacting to each move
+/// share it, so that they keep up when the snake makes several moves in a single frame.
+#[derive(RunCriteriaLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SnakeMoveTick;
+
 impl Plugin for SnakePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(SnakeBody::default())
-            .insert_resource(LastTailPos::default())
-            .add_event::<EatEvent>()
-            .add_startup_system(snake_spawn)
-            .add_system(snake_direction_input.before(snake_move))
-            .add_system(snake_pathfinding)
-            .add_system_set(
-                SystemSet::new()
-                    .with_run_criteria(FixedTimestep::step(MOVE_DELAY))
-                    .with_system(snake_move)
-                    .with_system(snake_eat.after(snake_move))
-                    .with_system(snake_grow.after(snake_eat)),
+        let headless = app.world.contains_resource::<Headless>();
+        app.init_resource::<Config>();
+        let move_delay = app.world.resource::<Config>().move_delay;
+        let mut movement = SystemSet::new()
+            .label(SnakeMovement)
+            .with_system(snake_control)
+            .with_system(snake_move.after(snake_control))
+            .with_system(snake_grow.after(snake_move));
+        // Without a window there is nobody to watch, so the snake moves as fast as possible
+        if headless {
+            movement = movement
+                .with_run_criteria(State::on_update(GameState::Playing).label(SnakeMoveTick));
+        } else {
+            movement =
+                movement.with_run_criteria(move_timer.chain(snake_move_tick).label(SnakeMoveTick));
+            app.insert_resource(Speed::Delay(move_delay))
+                .add_system(snake_direction_input.before(SnakeMovement));
+        }
+        app.init_resource::<Controller>()
+            .insert_resource(Simulation::default())
+            .insert_resource(SnakeBody::default())
+            .add_event::<StepOutcome>()
+            .add_system_set(movement)
+            .add_system(
+                snake_win
+                    .after(SnakeMovement)
+                    .with_run_criteria(SnakeMoveTick),
             );
     }
 }
 
-fn snake_pathfinding(
-    snake_body: ResMut<SnakeBody>,
-    pos_query: Query<&Pos>,
-    food_query: Query<&Pos, With<Food>>,
-    mut snake_head_query: Query<&mut SnakeHead>,
-) {
-    let food = food_query.single();
-    let mut body = Vec::with_capacity(snake_body.0.len());
-    for segment in snake_body.0.iter() {
-        body.push(*pos_query.get(*segment).unwrap());
+/// Run criterion chained after the [`move_timer`]. The snake moves on every tick while playing, and
+/// only when single stepping while paused. It never moves once the game is over, even if the state
+/// hasn't caught up yet, nor while a new game's snake is still being spawned.
+fn snake_move_tick(
+    In(tick): In<ShouldRun>,
+    game_state: Res<State<GameState>>,
+    sim: Res<Simulation>,
+    mut single_step: ResMut<SingleStep>,
+    head_query: Query<(), With<SnakeHead>>,
+) -> ShouldRun {
+    if sim.is_over() || head_query.is_empty() {
+        return ShouldRun::No;
+    }
+    match game_state.current() {
+        GameState::Playing => tick,
+        GameState::Paused if single_step.take() => ShouldRun::Yes,
+        _ => ShouldRun::No,
     }
-    let path = find_path(body, *food);
-    let mut head = snake_head_query.single_mut();
-    head.next_dir = path[0];
 }
 
-fn snake_spawn(mut commands: Commands, mut snake_body: ResMut<SnakeBody>) {
+fn snake_control(
+    sim: Res<Simulation>,
+    mut controller: ResMut<Controller>,
+    steering: Option<ResMut<Steering>>,
+    mut head_query: Query<&mut SnakeHead>,
+) {
+    head_query.single_mut().next_dir = match steering {
+        Some(mut steering) => steering.next_dir(&sim, controller.0.as_mut()),
+        None => controller.0.next_dir(&sim),
+    };
+}
+
+/// Spawns an entity for every segment of the simulation's snake.
+pub fn spawn_snake(commands: &mut Commands, sim: &Simulation) -> SnakeBody {
     let head = commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: SNAKE_HEAD_COLOR,
-                ..default()
-            },
-            transform: Transform {
-                scale: Vec3::new(10.0, 10.0, 10.0),
-                ..default()
-            },
-            ..default()
-        })
+        .spawn()
         .insert(SnakeHead::default())
-        .insert(SNAKE_HEAD_START_POS)
-        .insert(SNAKE_HEAD_SIZE)
+        .insert(sim.head())
         .id();
 
-    let mut body = Vec::with_capacity(SNAKE_STARTING_LEN);
+    let mut body = Vec::with_capacity(sim.len());
     body.push(head);
 
-    for i in 1..SNAKE_STARTING_LEN {
-        body.push(spawn_segment(
-            &mut commands,
-            Pos::new(SNAKE_HEAD_START_POS.x - i as i32, SNAKE_HEAD_START_POS.y),
-        ))
+    for &pos in sim.snake().iter().skip(1) {
+        body.push(spawn_segment(commands, pos));
     }
-    *snake_body = SnakeBody(body);
+    SnakeBody(body)
 }
 
+/// Passes on every arrow key press, rather than whichever key is held down when the snake moves, so
+/// that none are lost when several are pressed between two moves.
+/// The presses go to the player while they are steering, and otherwise to the controller, in case
+/// it is the human one.
 fn snake_direction_input(
     keyboard_input: Res<Input<KeyCode>>,
-    mut head_query: Query<&mut SnakeHead>,
+    mut controller: ResMut<Controller>,
+    mut steering: Option<ResMut<Steering>>,
 ) {
-    if let Some(mut head) = head_query.iter_mut().next() {
-        let dir = if keyboard_input.pressed(KeyCode::Left) {
-            Dir::Left
-        } else if keyboard_input.pressed(KeyCode::Right) {
-            Dir::Right
-        } else if keyboard_input.pressed(KeyCode::Up) {
-            Dir::Up
-        } else if keyboard_input.pressed(KeyCode::Down) {
-            Dir::Down
-        } else {
-            head.next_dir
-        };
-        if dir != head.prev_dir.opposite() {
-            head.next_dir = dir;
+    let keys = [
+        (KeyCode::Left, Dir::Left),
+        (KeyCode::Right, Dir::Right),
+        (KeyCode::Up, Dir::Up),
+        (KeyCode::Down, Dir::Down),
+    ];
+    for (key, dir) in keys {
+        if !keyboard_input.just_pressed(key) {
+            continue;
+        }
+        match steering.as_deref_mut() {
+            Some(steering) if steering.mode != SteeringMode::Ai => steering.key_input(dir),
+            _ => controller.0.key_input(dir),
         }
     }
 }
 
 fn snake_move(
     body: Res<SnakeBody>,
-    game_state: Res<GameState>,
-    mut last_tail_pos: ResMut<LastTailPos>,
-    mut head_query: Query<(Entity, &mut SnakeHead)>,
+    mut sim: ResMut<Simulation>,
+    mut step_writer: EventWriter<StepOutcome>,
+    head_query: Query<&SnakeHead>,
     mut pos_query: Query<&mut Pos>,
 ) {
-    if *game_state == GameState::Lost {
-        return;
+    let outcome = sim.step(head_query.single().next_dir);
+    // Mirror the simulation onto the snake's entities. If the snake grew, its new tail segment is
+    // spawned by `snake_grow`.
+    for (segment, &pos) in body.iter().zip(sim.snake()) {
+        *pos_query.get_mut(*segment).unwrap() = pos;
     }
-    let (head_entity, mut head) = head_query.single_mut();
-    // Finalize the movement direction
-    head.prev_dir = head.next_dir;
-
-    for (next_segment, prev_segment) in body.iter().rev().zip(body.iter().rev().skip(1)) {
-        let prev_pos = *pos_query.get(*prev_segment).unwrap();
-        let mut next_pos = pos_query.get_mut(*next_segment).unwrap();
-        *next_pos = prev_pos;
-    }
-    let mut head_pos = pos_query.get_mut(head_entity).unwrap();
-    match head.next_dir {
-        Dir::Left => {
-            head_pos.x -= 1;
-        }
-        Dir::Right => {
-            head_pos.x += 1;
-        }
-        Dir::Down => {
-            head_pos.y -= 1;
-        }
-        Dir::Up => {
-            head_pos.y += 1;
-        }
-    }
-    *last_tail_pos = LastTailPos(*pos_query.get(*body.last().unwrap()).unwrap());
+    step_writer.send(outcome);
 }
 
-fn snake_eat(
+fn snake_grow(
     mut commands: Commands,
-    mut eat_event_writer: EventWriter<EatEvent>,
-    food_pos_query: Query<(Entity, &Pos), With<Food>>,
-    head_pos_query: Query<&Pos, With<SnakeHead>>,
+    sim: Res<Simulation>,
+    mut body: ResMut<SnakeBody>,
+    mut step_reader: EventReader<StepOutcome>,
 ) {
-    let head_pos = head_pos_query.single();
-    for (food, food_pos) in &food_pos_query {
-        if food_pos == head_pos {
-            commands.entity(food).despawn();
-            eat_event_writer.send(EatEvent);
-        }
+    let grew = |outcome: &StepOutcome| matches!(outcome, StepOutcome::Ate | StepOutcome::Won);
+    if step_reader.iter().any(grew) {
+        body.push(spawn_segment(&mut commands, sim.tail()));
     }
 }
 
-fn snake_grow(
-    mut commands: Commands,
-    last_tail_pos: Res<LastTailPos>,
-    mut body: ResMut<SnakeBody>,
-    mut eat_event_reader: EventReader<EatEvent>,
+fn snake_win(
+    sim: Res<Simulation>,
+    mut game_state: ResMut<State<GameState>>,
+    mut step_reader: EventReader<StepOutcome>,
 ) {
-    if eat_event_reader.iter().next().is_some() {
-        body.push(spawn_segment(&mut commands, last_tail_pos.0));
+    if step_reader
+        .iter()
+        .any(|&outcome| outcome == StepOutcome::Won)
+    {
+        end_game(&mut game_state, GameState::Won);
+        println!(
+            "You win! The snake filled the arena in {} moves",
+            sim.moves()
+        );
     }
 }
 
 fn spawn_segment(commands: &mut Commands, pos: Pos) -> Entity {
-    commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: SNAKE_TAIL_COLOR,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(SnakeSegment)
-        .insert(pos)
-        .insert(SNAKE_TAIL_SEGMENT_SIZE)
-        .id()
+    commands.spawn().insert(SnakeSegment).insert(pos).id()
 }
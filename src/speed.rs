@@ -0,0 +1,171 @@
+use std::fmt;
+
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+use super::config::Config;
+use super::consts::*;
+
+/// Changes how fast the snake moves while the game runs. `+` and `-` speed it up and slow it down,
+/// `M` moves it on every frame, `T` turns on turbo, which makes several moves every frame, and `0`
+/// goes back to the speed from the config.
+pub struct SpeedPlugin;
+
+impl Plugin for SpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(speed_input);
+    }
+}
+
+/// How fast the snake moves when there is a window to watch it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    /// One move every so many seconds.
+    Delay(f64),
+    /// One move every frame.
+    Max,
+    /// Several moves every frame, only the last of which is drawn.
+    Turbo(u32),
+}
+
+impl Speed {
+    /// The next speed up, from the slowest delay through max speed to ever more turbo moves.
+    pub fn faster(self) -> Self {
+        match self {
+            Speed::Delay(delay) if delay / SPEED_FACTOR >= MIN_MOVE_DELAY => {
+                Speed::Delay(delay / SPEED_FACTOR)
+            }
+            Speed::Delay(_) => Speed::Max,
+            Speed::Max => Speed::Turbo(2),
+            Speed::Turbo(moves) => Speed::Turbo((moves * 2).min(MAX_TURBO_MOVES)),
+        }
+    }
+
+    /// The next speed down. A delay from the config that is already slower than
+    /// [`MAX_MOVE_DELAY`] is kept.
+    pub fn slower(self) -> Self {
+        match self {
+            Speed::Delay(delay) => {
+                Speed::Delay((delay * SPEED_FACTOR).min(MAX_MOVE_DELAY.max(delay)))
+            }
+            Speed::Max => Speed::Delay(MIN_MOVE_DELAY),
+            Speed::Turbo(moves) if moves / 2 > 1 => Speed::Turbo(moves / 2),
+            Speed::Turbo(_) => Speed::Max,
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Speed::Delay(delay) => write!(f, "{:.3}s per move", delay),
+            Speed::Max => write!(f, "one move per frame"),
+            Speed::Turbo(moves) => write!(f, "turbo, {} moves per frame", moves),
+        }
+    }
+}
+
+/// Progress towards the next moves of the snake.
+#[derive(Default)]
+pub struct MoveTimer {
+    /// Seconds since the last move, when moving on a delay.
+    elapsed: f64,
+    /// Moves left to make in this frame, at max speed or in turbo.
+    moves_left: u32,
+    /// Whether the timer was already checked in this frame.
+    looping: bool,
+}
+
+/// Run criterion for the snake's moves, like a `FixedTimestep` whose step follows the [`Speed`]
+/// resource. It asks to be checked again after every move, so that the snake can make several
+/// moves in a single frame.
+pub fn move_timer(time: Res<Time>, speed: Res<Speed>, mut timer: Local<MoveTimer>) -> ShouldRun {
+    let new_frame = !timer.looping;
+    let tick = match *speed {
+        Speed::Delay(delay) => {
+            if new_frame {
+                timer.elapsed += time.delta_seconds_f64();
+            }
+            let tick = timer.elapsed >= delay;
+            if tick {
+                timer.elapsed -= delay;
+            }
+            tick
+        }
+        Speed::Max | Speed::Turbo(_) => {
+            if new_frame {
+                timer.moves_left = match *speed {
+                    Speed::Turbo(moves) => moves,
+                    _ => 1,
+                };
+                timer.elapsed = 0.0;
+            }
+            let tick = timer.moves_left > 0;
+            if tick {
+                timer.moves_left -= 1;
+            }
+            tick
+        }
+    };
+    timer.looping = tick;
+    if tick {
+        ShouldRun::YesAndCheckAgain
+    } else {
+        ShouldRun::No
+    }
+}
+
+fn speed_input(keyboard_input: Res<Input<KeyCode>>, config: Res<Config>, mut speed: ResMut<Speed>) {
+    let pressed = |keys: &[KeyCode]| keys.iter().any(|&key| keyboard_input.just_pressed(key));
+    let new_speed = if pressed(&[KeyCode::Equals, KeyCode::Plus, KeyCode::NumpadAdd]) {
+        speed.faster()
+    } else if pressed(&[KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        speed.slower()
+    } else if pressed(&[KeyCode::M]) {
+        Speed::Max
+    } else if pressed(&[KeyCode::T]) {
+        Speed::Turbo(TURBO_MOVES)
+    } else if pressed(&[KeyCode::Key0, KeyCode::Numpad0]) {
+        Speed::Delay(config.move_delay)
+    } else {
+        return;
+    };
+    if new_speed != *speed {
+        *speed = new_speed;
+        println!("Speed: {}", new_speed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn speed_goes_up_and_down_in_order() {
+        let mut speed = Speed::Delay(MIN_MOVE_DELAY * 2.0);
+        speed = speed.faster();
+        assert!(matches!(speed, Speed::Delay(delay) if delay < MIN_MOVE_DELAY * 2.0));
+        speed = speed.faster();
+        assert_eq!(speed, Speed::Max);
+        speed = speed.faster();
+        assert_eq!(speed, Speed::Turbo(2));
+        speed = speed.faster();
+        assert_eq!(speed, Speed::Turbo(4));
+        assert_eq!(speed.slower().slower(), Speed::Max);
+        assert_eq!(
+            Speed::Turbo(MAX_TURBO_MOVES).faster(),
+            Speed::Turbo(MAX_TURBO_MOVES)
+        );
+    }
+
+    #[test]
+    fn slowing_down_keeps_a_slow_config_delay() {
+        assert_eq!(
+            Speed::Delay(MAX_MOVE_DELAY).slower(),
+            Speed::Delay(MAX_MOVE_DELAY)
+        );
+        assert_eq!(
+            Speed::Delay(MAX_MOVE_DELAY * 2.0).slower(),
+            Speed::Delay(MAX_MOVE_DELAY * 2.0)
+        );
+    }
+}
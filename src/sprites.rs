@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use super::config::{color, Config};
+
+/// Gives the snake and food entities their sprites. Game entities are spawned with only their
+/// logical components, so that the game can also run without a window.
+pub struct SpritesPlugin;
+
+impl Plugin for SpritesPlugin {
+    fn build(&self, app: &mut App) {
+        // Sprites are added at the start of the next frame, once the entity has been spawned, so
+        // that it already has a position by the time it is first drawn.
+        app.add_system_set_to_stage(
+            CoreStage::PreUpdate,
+            SystemSet::new()
+                .with_system(snake_head_sprite)
+                .with_system(snake_segment_sprite)
+                .with_system(food_sprite),
+        );
+    }
+}
+
+fn snake_head_sprite(
+    mut commands: Commands,
+    config: Res<Config>,
+    query: Query<Entity, Added<SnakeHead>>,
+) {
+    for entity in &query {
+        commands
+            .entity(entity)
+            .insert_bundle(sprite_bundle(color(config.colors.snake_head)))
+            .insert(Size(config.sizes.snake_head));
+    }
+}
+
+fn snake_segment_sprite(
+    mut commands: Commands,
+    config: Res<Config>,
+    query: Query<Entity, Added<SnakeSegment>>,
+) {
+    for entity in &query {
+        commands
+            .entity(entity)
+            .insert_bundle(sprite_bundle(color(config.colors.snake_tail)))
+            .insert(Size(config.sizes.snake_tail));
+    }
+}
+
+fn food_sprite(mut commands: Commands, config: Res<Config>, query: Query<Entity, Added<Food>>) {
+    for entity in &query {
+        commands
+            .entity(entity)
+            .insert_bundle(sprite_bundle(color(config.colors.food)))
+            .insert(Size(config.sizes.food));
+    }
+}
+
+fn sprite_bundle(color: Color) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite { color, ..default() },
+        ..default()
+    }
+}
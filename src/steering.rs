@@ -0,0 +1,136 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use super::components::*;
+use super::controller::{HumanController, SnakeController};
+use super::pathfinding::is_safe;
+use super::simulation::Simulation;
+
+/// Lets the player take the snake over from the AI with the arrow keys, on their own or with the
+/// AI stepping in to avoid crashes. `Tab` cycles through the [`SteeringMode`]s.
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Steering>()
+            .add_system(steering_input)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(steering_reset));
+    }
+}
+
+/// Who steers the snake.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum SteeringMode {
+    /// The AI plays on its own.
+    #[default]
+    Ai,
+    /// The player steers, but the AI makes any move the player's would have killed the snake with.
+    Assist,
+    /// The player steers, crashes and all.
+    Human,
+}
+
+/// How the snake is steered, along with the player's own controller. Also a resource.
+#[derive(Default)]
+pub struct Steering {
+    pub mode: SteeringMode,
+    human: HumanController,
+    /// Whether the player has steered any of the current game's moves.
+    player_steered: bool,
+}
+
+impl SteeringMode {
+    fn next(self) -> Self {
+        match self {
+            SteeringMode::Ai => SteeringMode::Assist,
+            SteeringMode::Assist => SteeringMode::Human,
+            SteeringMode::Human => SteeringMode::Ai,
+        }
+    }
+}
+
+impl fmt::Display for SteeringMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SteeringMode::Ai => write!(f, "AI"),
+            SteeringMode::Assist => write!(f, "assisted"),
+            SteeringMode::Human => write!(f, "human"),
+        }
+    }
+}
+
+impl Steering {
+    /// Chooses the direction of the snake's next move, asking `ai` or the player depending on the
+    /// mode.
+    pub fn next_dir(&mut self, sim: &Simulation, ai: &mut dyn SnakeController) -> Dir {
+        match self.mode {
+            SteeringMode::Ai => ai.next_dir(sim),
+            SteeringMode::Assist => {
+                self.player_steered = true;
+                let dir = self.human.next_dir(sim);
+                let snake: Vec<Pos> = sim.snake().iter().copied().collect();
+                if is_safe(sim.arena(), &snake, dir) {
+                    dir
+                } else {
+                    ai.next_dir(sim)
+                }
+            }
+            SteeringMode::Human => {
+                self.player_steered = true;
+                self.human.next_dir(sim)
+            }
+        }
+    }
+
+    /// Receives a direction key press, while the player is steering.
+    pub fn key_input(&mut self, dir: Dir) {
+        self.human.key_input(dir);
+    }
+
+    /// Whether the player has steered any of the current game's moves, so that it isn't the AI's
+    /// game alone.
+    pub fn player_steered(&self) -> bool {
+        self.player_steered
+    }
+}
+
+fn steering_input(keyboard_input: Res<Input<KeyCode>>, mut steering: ResMut<Steering>) {
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        steering.mode = steering.mode.next();
+        // Turns pressed for the last mode aren't meant for this one
+        steering.human.reset();
+        println!("Steering: {}", steering.mode);
+    }
+}
+
+fn steering_reset(mut steering: ResMut<Steering>) {
+    steering.human.reset();
+    steering.player_steered = false;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::controller::controller_by_name;
+
+    #[test]
+    fn assist_vetoes_fatal_moves() {
+        // Heading right, into the wall
+        let sim = Simulation::new(Arena::new(5, 5), Pos::new(4, 2), 3, Dir::Right);
+        let mut ai = controller_by_name("greedy").unwrap();
+        let mut steering = Steering::default();
+        assert!(!steering.player_steered());
+
+        steering.mode = SteeringMode::Human;
+        assert_eq!(steering.next_dir(&sim, ai.as_mut()), Dir::Right);
+        assert!(steering.player_steered());
+
+        steering.mode = SteeringMode::Assist;
+        let dir = steering.next_dir(&sim, ai.as_mut());
+        assert!(dir == Dir::Up || dir == Dir::Down);
+        // Safe moves are left to the player
+        steering.key_input(Dir::Up);
+        assert_eq!(steering.next_dir(&sim, ai.as_mut()), Dir::Up);
+    }
+}